@@ -0,0 +1,165 @@
+//! Compare a run against a previously saved JSON report.
+//!
+//! The baseline is the output of `--format json`. Atoms are matched by result
+//! name, so instantiations such as "power(p=2)" are compared individually.
+//! Atoms missing from the baseline count as newly failing if they fail now.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::ValidationResult;
+
+/// How a run differs from its baseline
+#[derive(Debug, Default)]
+pub struct BaselineDiff {
+    /// Atoms that fail now but passed (or were absent) in the baseline
+    pub new_failures: Vec<String>,
+    /// Atoms that pass now but failed in the baseline
+    pub new_passes: Vec<String>,
+    /// Atoms failing in both runs, with the failing check names before and after
+    pub changed: Vec<(String, Vec<String>, Vec<String>)>,
+}
+
+impl BaselineDiff {
+    pub fn is_empty(&self) -> bool {
+        self.new_failures.is_empty() && self.new_passes.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Load a JSON report written by `--format json`
+pub fn load_baseline(path: &Path) -> Result<Vec<ValidationResult>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn failing_checks(result: &ValidationResult) -> Vec<String> {
+    let names: BTreeSet<&str> =
+        result.checks.iter().filter(|c| !c.passed).map(|c| c.name.as_str()).collect();
+    names.into_iter().map(String::from).collect()
+}
+
+/// Diff `results` against `baseline`, with each list sorted by atom name
+pub fn diff_baseline(baseline: &[ValidationResult], results: &[ValidationResult]) -> BaselineDiff {
+    let before: HashMap<&str, &ValidationResult> =
+        baseline.iter().map(|r| (r.atom_name.as_str(), r)).collect();
+
+    let mut diff = BaselineDiff::default();
+    for result in results {
+        let name = result.atom_name.clone();
+        match (before.get(result.atom_name.as_str()), result.passed) {
+            (Some(old), true) if !old.passed => diff.new_passes.push(name),
+            (Some(old), false) if !old.passed => {
+                let (was, now) = (failing_checks(old), failing_checks(result));
+                if was != now {
+                    diff.changed.push((name, was, now));
+                }
+            }
+            (_, false) => diff.new_failures.push(name),
+            _ => {}
+        }
+    }
+
+    diff.new_failures.sort();
+    diff.new_passes.sort();
+    diff.changed.sort();
+    diff
+}
+
+/// Print the regression section for a baseline comparison
+pub fn print_baseline_diff<W: Write>(diff: &BaselineDiff, out: &mut W) -> io::Result<()> {
+    writeln!(out, "REGRESSIONS AGAINST BASELINE:")?;
+    writeln!(out, "{}", "-".repeat(40))?;
+    if diff.is_empty() {
+        writeln!(out, "  no changes")?;
+        return writeln!(out);
+    }
+
+    if !diff.new_failures.is_empty() {
+        writeln!(out, "\n  Newly failing ({}):", diff.new_failures.len())?;
+        for name in &diff.new_failures {
+            writeln!(out, "    {}", name)?;
+        }
+    }
+    if !diff.new_passes.is_empty() {
+        writeln!(out, "\n  Newly passing ({}):", diff.new_passes.len())?;
+        for name in &diff.new_passes {
+            writeln!(out, "    {}", name)?;
+        }
+    }
+    if !diff.changed.is_empty() {
+        writeln!(out, "\n  Failing checks changed ({}):", diff.changed.len())?;
+        for (name, was, now) in &diff.changed {
+            writeln!(out, "    {}: {} -> {}", name, was.join(", "), now.join(", "))?;
+        }
+    }
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::ValidationCheck;
+
+    /// A result for `name` whose checks named in `failing` fail
+    fn result(name: &str, failing: &[&str]) -> ValidationResult {
+        let mut checks: Vec<ValidationCheck> = failing
+            .iter()
+            .map(|check| ValidationCheck {
+                name: check.to_string(),
+                passed: false,
+                message: String::new(),
+            })
+            .collect();
+        checks.push(ValidationCheck {
+            name: "exists".to_string(),
+            passed: true,
+            message: String::new(),
+        });
+        ValidationResult {
+            atom_name: name.to_string(),
+            passed: failing.is_empty(),
+            checks,
+            duration: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn diff_baseline_classifies_each_atom() {
+        let baseline = [
+            result("abs", &[]),
+            result("exp", &["sign"]),
+            result("log", &["curvature"]),
+            result("sqrt", &["curvature"]),
+            result("power(p=2)", &[]),
+        ];
+        let results = [
+            result("sqrt", &["curvature"]), // still failing the same way
+            result("power(p=2)", &["shape"]), // regressed
+            result("log", &["sign"]), // failing differently
+            result("exp", &[]), // fixed
+            result("abs", &[]), // still passing
+            result("huber", &["curvature"]), // new and failing
+            result("inv_pos", &[]), // new and passing
+        ];
+
+        let diff = diff_baseline(&baseline, &results);
+        assert_eq!(diff.new_failures, ["huber", "power(p=2)"]);
+        assert_eq!(diff.new_passes, ["exp"]);
+        assert_eq!(
+            diff.changed,
+            [("log".to_string(), vec!["curvature".to_string()], vec!["sign".to_string()])]
+        );
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn diff_baseline_of_identical_runs_is_empty() {
+        let run = [result("abs", &[]), result("log", &["curvature", "sign"])];
+        assert!(diff_baseline(&run, &run).is_empty());
+    }
+}
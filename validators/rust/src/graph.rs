@@ -0,0 +1,203 @@
+//! Cone-formulation checks for the specs' graph implementations.
+//!
+//! For a convex atom f with a conic epigraph in specs/atoms.yaml, the set
+//! { (t, x) : t >= f(x) } must coincide with the set cut out by the cone
+//! constraints. We check this by sampling x, bisecting for the smallest t that
+//! satisfies the cone memberships, and comparing it to cvxrust's value of f(x).
+//!
+//! The cone memberships are the spec's formulation re-implemented here, not
+//! cvxrust's canonicalizer output, so a pass validates the spec rather than
+//! the canonicalization cvxrust actually emits.
+
+use cvxrust::prelude::*;
+use nalgebra::DMatrix;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{attach_check, evaluate, vector_constant, ValidationCheck, ValidationResult};
+
+/// Shape of the sampled sigma_max argument
+#[cfg(feature = "sigma-max")]
+const SIGMA_ROWS: usize = 3;
+#[cfg(feature = "sigma-max")]
+const SIGMA_COLS: usize = 2;
+
+/// Fixed seed, reset for each atom so a failing sample can be reproduced
+/// regardless of which other atoms were selected
+const SEED: u64 = 0x5eed_cafe;
+
+/// Number of random points sampled per atom
+const SAMPLES: usize = 20;
+
+/// Relative tolerance between the bisected epigraph bound and f(x)
+const TOLERANCE: f64 = 1e-6;
+
+/// Denominator used for quad_over_lin(x, y)
+const QOL_Y: f64 = 2.0;
+
+/// The conic (epigraph) implementation of a single atom
+struct GraphImpl {
+    atom: &'static str,
+    /// Shape of the sampled argument
+    arg_shape: (usize, usize),
+    /// Build the atom over a constant argument
+    build: fn(&Expr) -> Expr,
+    /// Whether (t, x) satisfies the atom's cone constraints, with x column-major
+    contains: fn(f64, &[f64]) -> bool,
+}
+
+fn in_soc(t: f64, x: &[f64]) -> bool {
+    x.iter().map(|v| v * v).sum::<f64>().sqrt() <= t
+}
+
+/// Rotated SOC as in cones.yaml: ||x||^2 <= 2*s*t, s, t >= 0
+fn in_rsoc(s: f64, t: f64, x: &[f64]) -> bool {
+    s >= 0.0 && t >= 0.0 && x.iter().map(|v| v * v).sum::<f64>() <= 2.0 * s * t
+}
+
+/// Exponential cone as in cones.yaml: y * exp(x / y) <= z, y > 0
+fn in_exp_cone(x: f64, y: f64, z: f64) -> bool {
+    y > 0.0 && y * (x / y).exp() <= z
+}
+
+/// PSD test via the smallest eigenvalue of a symmetric matrix
+#[cfg(feature = "sigma-max")]
+fn in_psd(m: DMatrix<f64>) -> bool {
+    m.symmetric_eigenvalues().min() >= -1e-12
+}
+
+/// The sigma_max LMI [[t*I, A], [A^T, t*I]] >> 0 for column-major A
+#[cfg(feature = "sigma-max")]
+fn in_sigma_max_lmi(t: f64, a: &[f64]) -> bool {
+    let a = DMatrix::from_column_slice(SIGMA_ROWS, SIGMA_COLS, a);
+    let n = SIGMA_ROWS + SIGMA_COLS;
+    let mut block = DMatrix::identity(n, n) * t;
+    block.view_mut((0, SIGMA_ROWS), (SIGMA_ROWS, SIGMA_COLS)).copy_from(&a);
+    block.view_mut((SIGMA_ROWS, 0), (SIGMA_COLS, SIGMA_ROWS)).copy_from(&a.transpose());
+    in_psd(block)
+}
+
+fn graph_impls() -> Vec<GraphImpl> {
+    vec![
+        GraphImpl {
+            atom: "norm2",
+            arg_shape: (5, 1),
+            build: |x| norm2(x),
+            // [t; x] in SOC
+            contains: in_soc,
+        },
+        GraphImpl {
+            atom: "quadOverLin",
+            arg_shape: (5, 1),
+            build: |x| quad_over_lin(x, &vector_constant(&[QOL_Y])),
+            // [t; y; sqrt(2) * x] in RSOC
+            contains: |t, x| {
+                let scaled: Vec<f64> = x.iter().map(|v| v * std::f64::consts::SQRT_2).collect();
+                in_rsoc(t, QOL_Y, &scaled)
+            },
+        },
+        GraphImpl {
+            atom: "exp",
+            arg_shape: (1, 1),
+            build: |x| exp(x),
+            // (x, 1, t) in ExpCone
+            contains: |t, x| in_exp_cone(x[0], 1.0, t),
+        },
+        #[cfg(feature = "sigma-max")]
+        GraphImpl {
+            atom: "sigma_max",
+            arg_shape: (SIGMA_ROWS, SIGMA_COLS),
+            build: |x| sigma_max(x),
+            contains: in_sigma_max_lmi,
+        },
+    ]
+}
+
+/// Smallest t with `contains(t, x)`, assuming the feasible t form a ray [t*, inf)
+fn minimal_epigraph_bound(contains: fn(f64, &[f64]) -> bool, x: &[f64]) -> Option<f64> {
+    let mut hi = 1.0;
+    let mut steps = 0;
+    while !contains(hi, x) {
+        hi *= 2.0;
+        steps += 1;
+        if steps > 200 {
+            return None;
+        }
+    }
+
+    let mut lo = hi - 1.0;
+    steps = 0;
+    while contains(lo, x) {
+        lo -= 2.0 * (hi - lo);
+        steps += 1;
+        if steps > 200 {
+            return None;
+        }
+    }
+
+    for _ in 0..200 {
+        let mid = 0.5 * (lo + hi);
+        if contains(mid, x) {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Some(hi)
+}
+
+fn check_graph_impl(graph: &GraphImpl) -> ValidationCheck {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for _ in 0..SAMPLES {
+        let (rows, cols) = graph.arg_shape;
+        let x: Vec<f64> = (0..rows * cols).map(|_| rng.gen_range(-3.0..3.0)).collect();
+        let arg = constant(DMatrix::from_column_slice(rows, cols, &x));
+
+        let value = match evaluate(&(graph.build)(&arg)) {
+            Some(v) => v[0],
+            None => {
+                return ValidationCheck {
+                    name: "cone_formulation".to_string(),
+                    passed: false,
+                    message: format!("could not evaluate {} at x = {:?}", graph.atom, x),
+                }
+            }
+        };
+
+        let Some(bound) = minimal_epigraph_bound(graph.contains, &x) else {
+            return ValidationCheck {
+                name: "cone_formulation".to_string(),
+                passed: false,
+                message: format!("epigraph is empty or unbounded below at x = {:?}", x),
+            };
+        };
+
+        if (bound - value).abs() > TOLERANCE * value.abs().max(1.0) {
+            return ValidationCheck {
+                name: "cone_formulation".to_string(),
+                passed: false,
+                message: format!(
+                    "at x = {:?}: minimal epigraph t = {}, but f(x) = {}",
+                    x, bound, value
+                ),
+            };
+        }
+    }
+
+    ValidationCheck {
+        name: "cone_formulation".to_string(),
+        passed: true,
+        message: format!("epigraph matches f(x) on {} samples", SAMPLES),
+    }
+}
+
+/// Run the cone-formulation pass for the selected atoms and attach its checks to `results`
+pub fn check_graphs(results: &mut Vec<ValidationResult>, selected: &dyn Fn(&str) -> bool) {
+    for graph in graph_impls() {
+        if !selected(graph.atom) {
+            continue;
+        }
+        let check = check_graph_impl(&graph);
+        attach_check(results, graph.atom, check);
+    }
+}
@@ -0,0 +1,3084 @@
+//! Validate cvxrust implementation against CVX-Core specifications.
+//!
+//! This validator tests that cvxrust's atoms behave according to the
+//! canonical specifications in specs/atoms.yaml. The `cvx-validator` binary is
+//! a thin command-line wrapper; other harnesses can call `load_specs` and
+//! `validate_all` directly, registering their own atoms in an `AtomRegistry`.
+
+pub mod baseline;
+pub mod graph;
+pub mod parity;
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+use cvxrust::prelude::*;
+use nalgebra::DMatrix;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Specification for a single atom from atoms.yaml
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AtomSpec {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub curvature: CurvatureSpec,
+    /// Sign of the result. A rule mapping (e.g. add's `rule: combine`) names no
+    /// single sign, so it reads as "unknown" and the sign check accepts any result.
+    #[serde(default, deserialize_with = "string_or_rule")]
+    pub sign: String,
+    #[serde(default)]
+    pub arity: String,
+    #[serde(default)]
+    pub shape: String,
+    #[serde(default, deserialize_with = "string_or_list")]
+    pub dcp_requires: Option<String>,
+    #[serde(default)]
+    pub monotonicity: MonotonicitySpec,
+    #[serde(default)]
+    pub test_vectors: Vec<TestVector>,
+    #[serde(default)]
+    pub instantiations: Vec<Instantiation>,
+    /// Smooth on its domain, so its gradient is checked against finite differences
+    #[serde(default)]
+    pub differentiable: bool,
+    /// Tolerance for this atom's numeric checks that would otherwise use
+    /// `ValidationOptions::tol`; checks with their own fixed tolerance keep it
+    #[serde(default)]
+    pub numeric_tol: Option<f64>,
+    /// Skip the test vectors and the gradient check for this atom
+    #[serde(default)]
+    pub skip_numeric: bool,
+    /// Spec file that last defined or extended this atom
+    #[serde(skip)]
+    pub source: PathBuf,
+    /// Why this atom's entry failed to parse; reported as a failed "spec_parse" check
+    #[serde(skip)]
+    pub parse_error: Option<String>,
+}
+
+impl AtomSpec {
+    /// Tolerance for a numeric comparison whose default is `default`
+    pub fn tol(&self, default: f64) -> f64 {
+        self.numeric_tol.unwrap_or(default)
+    }
+}
+
+/// A string field that may instead be given as a rule mapping (e.g. a
+/// conditional sign); rules read as "unknown", which no sign check rejects
+fn string_or_rule<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let value = serde_yaml::Value::deserialize(deserializer)?;
+    Ok(value.as_str().unwrap_or("unknown").to_string())
+}
+
+/// An optional string field that may be a list of strings, joined with ", "
+fn string_or_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    match Option::<serde_yaml::Value>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(serde_yaml::Value::String(s)) => Ok(Some(s)),
+        Some(serde_yaml::Value::Sequence(items)) => {
+            let items: Option<Vec<&str>> = items.iter().map(|item| item.as_str()).collect();
+            items
+                .map(|items| Some(items.join(", ")))
+                .ok_or_else(|| D::Error::custom("expected a list of strings"))
+        }
+        Some(other) => Err(D::Error::custom(format!("expected a string or list, got {:?}", other))),
+    }
+}
+
+/// One parameter setting of a parameterized atom, with its expected curvature
+#[derive(Debug, Clone, Deserialize)]
+pub struct Instantiation {
+    pub params: BTreeMap<String, f64>,
+    /// Expected curvature; when omitted it is resolved from the atom's conditional rule
+    #[serde(default)]
+    pub curvature: Option<String>,
+    /// Argument domain the curvature holds on, e.g. "positive"
+    #[serde(default)]
+    pub domain: Option<String>,
+}
+
+impl Instantiation {
+    /// Result name for this instantiation, e.g. "power(p=2)"
+    pub fn label(&self, atom_name: &str) -> String {
+        let params: Vec<String> = self.params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        format!("{}({})", atom_name, params.join(", "))
+    }
+}
+
+/// A reference evaluation: the atom applied to `input` must give `expected`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestVector {
+    pub input: Vec<f64>,
+    pub expected: NumericValue,
+}
+
+/// A scalar or array value in atoms.yaml
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum NumericValue {
+    Scalar(f64),
+    Array(Vec<f64>),
+}
+
+impl NumericValue {
+    pub fn to_vec(&self) -> Vec<f64> {
+        match self {
+            NumericValue::Scalar(v) => vec![*v],
+            NumericValue::Array(v) => v.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(untagged)]
+pub enum CurvatureSpec {
+    Simple(String),
+    Complex(CurvatureRule),
+    #[default]
+    Unknown,
+}
+
+impl CurvatureSpec {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CurvatureSpec::Simple(s) => s.as_str(),
+            _ => "unknown",
+        }
+    }
+
+    /// Expected curvature for the given parameter values, if the spec decides it
+    pub fn resolve(&self, params: &BTreeMap<String, f64>) -> Option<&str> {
+        match self {
+            CurvatureSpec::Simple(s) => Some(s.as_str()),
+            CurvatureSpec::Complex(rule) => rule.resolve(params),
+            CurvatureSpec::Unknown => None,
+        }
+    }
+}
+
+/// A curvature given as a rule, such as power's conditional cases on `p`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CurvatureRule {
+    #[serde(default)]
+    pub rule: String,
+    #[serde(default)]
+    pub cases: Vec<CurvatureCase>,
+    #[serde(default)]
+    pub formula: Option<String>,
+    #[serde(default)]
+    pub requires: Option<String>,
+}
+
+/// One `if`/`then` (or trailing `else`) case of a conditional rule
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurvatureCase {
+    #[serde(default, rename = "if")]
+    pub condition: Option<String>,
+    #[serde(default, rename = "then")]
+    pub curvature: Option<String>,
+    #[serde(default, rename = "else")]
+    pub otherwise: Option<String>,
+}
+
+impl CurvatureRule {
+    /// The first case whose condition holds for `params`.
+    ///
+    /// Returns `None` for non-conditional rules, and when a condition depends
+    /// on something other than numeric parameters (e.g. "P is PSD").
+    pub fn resolve(&self, params: &BTreeMap<String, f64>) -> Option<&str> {
+        if self.rule != "conditional" {
+            return None;
+        }
+        for case in &self.cases {
+            match &case.condition {
+                Some(condition) => {
+                    if eval_condition(condition, params)? {
+                        return case.curvature.as_deref();
+                    }
+                }
+                None => return case.otherwise.as_deref(),
+            }
+        }
+        None
+    }
+}
+
+/// Evaluate a condition such as "p >= 1 or p < 0" or "0 < p < 1".
+///
+/// Supports `or`, `and` and chained comparisons over numbers and parameter
+/// names; anything else yields `None`.
+fn eval_condition(condition: &str, params: &BTreeMap<String, f64>) -> Option<bool> {
+    let operand = |token: &str| token.parse::<f64>().ok().or_else(|| params.get(token).copied());
+
+    let mut any = false;
+    for disjunct in condition.split(" or ") {
+        let mut all = true;
+        for comparison in disjunct.split(" and ") {
+            let tokens: Vec<&str> = comparison.split_whitespace().collect();
+            if tokens.len() < 3 || tokens.len() % 2 == 0 {
+                return None;
+            }
+            for window in tokens.windows(3).step_by(2) {
+                let (lhs, rhs) = (operand(window[0])?, operand(window[2])?);
+                all &= match window[1] {
+                    "<" => lhs < rhs,
+                    "<=" => lhs <= rhs,
+                    ">" => lhs > rhs,
+                    ">=" => lhs >= rhs,
+                    "==" => lhs == rhs,
+                    "!=" => lhs != rhs,
+                    _ => return None,
+                };
+            }
+        }
+        any |= all;
+    }
+    Some(any)
+}
+
+/// Monotonicity of an atom in its argument: increasing, decreasing or none
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(untagged)]
+pub enum MonotonicitySpec {
+    Simple(String),
+    Complex(HashMap<String, serde_yaml::Value>),
+    #[default]
+    Unknown,
+}
+
+impl MonotonicitySpec {
+    pub fn as_str(&self) -> &str {
+        match self {
+            MonotonicitySpec::Simple(s) => s.as_str(),
+            _ => "unknown",
+        }
+    }
+}
+
+/// Atom sections of atoms.yaml, with each atom parsed separately so one bad
+/// entry doesn't hide the rest
+#[derive(Debug, Deserialize)]
+struct AtomsYaml {
+    #[serde(default)]
+    affine_atoms: HashMap<String, serde_yaml::Value>,
+    #[serde(default)]
+    convex_atoms: HashMap<String, serde_yaml::Value>,
+    #[serde(default)]
+    concave_atoms: HashMap<String, serde_yaml::Value>,
+}
+
+/// Parse one atom entry, recording a failure on the spec instead of aborting
+fn parse_atom(value: serde_yaml::Value) -> AtomSpec {
+    serde_yaml::from_value(value).unwrap_or_else(|e| AtomSpec {
+        parse_error: Some(e.to_string()),
+        ..AtomSpec::default()
+    })
+}
+
+/// Result of a single validation check
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationCheck {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+/// Result of validating a single atom
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationResult {
+    pub atom_name: String,
+    pub passed: bool,
+    pub checks: Vec<ValidationCheck>,
+    /// Wall-clock time spent in `validate_atom`
+    #[serde(skip)]
+    pub duration: Duration,
+}
+
+impl ValidationResult {
+    pub fn failed_checks(&self) -> Vec<&ValidationCheck> {
+        self.checks.iter().filter(|c| !c.passed).collect()
+    }
+}
+
+/// Settings shared by every atom's checks
+#[derive(Debug)]
+pub struct ValidationOptions {
+    /// Tolerance for numeric checks, unless an atom sets numeric_tol
+    pub tol: f64,
+    /// Fail unknown curvature or sign where the spec gives a concrete value
+    pub strict: bool,
+    /// Test-expression constructors, including any registered by the caller
+    pub registry: AtomRegistry,
+    /// Worker threads for validating atoms; `None` uses one per core and
+    /// `Some(1)` validates serially
+    pub jobs: Option<usize>,
+    /// Random affine arguments tried per atom by the fuzz check; 0 disables it
+    pub fuzz: usize,
+    /// Include extra detail, such as the effective tolerance, in check messages
+    pub verbose: bool,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        ValidationOptions {
+            tol: 1e-9,
+            strict: false,
+            registry: AtomRegistry::default(),
+            jobs: None,
+            fuzz: 0,
+            verbose: false,
+        }
+    }
+}
+
+/// Which atoms to validate; an empty filter selects every atom
+#[derive(Debug, Default)]
+pub struct AtomFilter {
+    /// Only these atoms (all atoms if empty)
+    pub atoms: Vec<String>,
+    /// Only atoms in these spec categories (all categories if empty)
+    pub categories: Vec<String>,
+    /// Atoms to skip
+    pub exclude: Vec<String>,
+}
+
+impl AtomFilter {
+    pub fn selects(&self, atom_name: &str, category: Option<&str>) -> bool {
+        if !self.atoms.is_empty() && !self.atoms.iter().any(|a| a == atom_name) {
+            return false;
+        }
+        if !self.categories.is_empty() {
+            match category {
+                Some(c) if self.categories.iter().any(|want| want == c) => {}
+                _ => return false,
+            }
+        }
+        !self.exclude.iter().any(|a| a == atom_name)
+    }
+}
+
+/// Append a check to the named atom's result, creating the result if needed
+fn attach_check(results: &mut Vec<ValidationResult>, atom_name: &str, check: ValidationCheck) {
+    match results.iter_mut().find(|r| r.atom_name == atom_name) {
+        Some(result) => {
+            result.passed &= check.passed;
+            result.checks.push(check);
+        }
+        None => results.push(ValidationResult {
+            atom_name: atom_name.to_string(),
+            passed: check.passed,
+            checks: vec![check],
+            duration: Duration::ZERO,
+        }),
+    }
+}
+
+/// Insert an atom spec, keeping the first definition if the name is already taken.
+///
+/// A duplicate is a warning, or an error when `strict` is set.
+fn insert_spec(
+    specs: &mut HashMap<String, (AtomSpec, &'static str)>,
+    name: String,
+    spec: AtomSpec,
+    category: &'static str,
+    strict: bool,
+) -> Result<(), String> {
+    if let Some((_, first)) = specs.get(&name) {
+        let msg = format!(
+            "atom '{}' is defined in both {}_atoms and {}_atoms; using the {} definition",
+            name, first, category, first
+        );
+        if strict {
+            return Err(msg);
+        }
+        eprintln!("warning: {}", msg);
+        return Ok(());
+    }
+    specs.insert(name, (spec, category));
+    Ok(())
+}
+
+/// Sections of atoms.yaml that hold atom definitions
+const ATOM_SECTIONS: [&str; 3] = ["affine_atoms", "convex_atoms", "concave_atoms"];
+
+/// Read a spec file as an untyped YAML value
+fn read_yaml(path: &Path) -> Result<serde_yaml::Value, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_yaml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Merge `overlay` into `base`: mappings merge key by key, anything else is replaced
+fn merge_yaml(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_yaml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Record `path` as the source of every atom defined in `data`
+fn record_sources(data: &serde_yaml::Value, path: &Path, sources: &mut HashMap<String, PathBuf>) {
+    for section in ATOM_SECTIONS {
+        let Some(atoms) = data.get(section).and_then(|atoms| atoms.as_mapping()) else {
+            continue;
+        };
+        for name in atoms.keys().filter_map(|name| name.as_str()) {
+            sources.insert(name.to_string(), path.to_path_buf());
+        }
+    }
+}
+
+/// Load atom specifications from atoms.yaml, then apply each overlay file in order.
+///
+/// Overlays have the same layout as atoms.yaml and are merged field by field,
+/// so an overlay can add `test_vectors` to an existing atom without restating
+/// its curvature and sign. An atom name that appears in more than one
+/// category is reported as a warning, or as an error when `strict` is set.
+pub fn load_specs(
+    specs_dir: &Path,
+    overlays: &[PathBuf],
+    strict: bool,
+) -> Result<HashMap<String, (AtomSpec, &'static str)>, String> {
+    if !specs_dir.is_dir() {
+        return Err(format!("Specs directory {} does not exist", specs_dir.display()));
+    }
+    let atoms_path = specs_dir.join("atoms.yaml");
+    let mut merged = read_yaml(&atoms_path)?;
+    let mut sources = HashMap::new();
+    record_sources(&merged, &atoms_path, &mut sources);
+
+    for path in overlays {
+        let overlay = read_yaml(path)?;
+        record_sources(&overlay, path, &mut sources);
+        merge_yaml(&mut merged, overlay);
+    }
+
+    let data: AtomsYaml = serde_yaml::from_value(merged)
+        .map_err(|e| format!("Failed to parse {}: {}", atoms_path.display(), e))?;
+
+    let mut specs = HashMap::new();
+    let mut insert = |name: String, mut spec: AtomSpec, category: &'static str| {
+        spec.source = sources.get(&name).cloned().unwrap_or_else(|| atoms_path.clone());
+        insert_spec(&mut specs, name, spec, category, strict)
+    };
+
+    for (name, value) in data.affine_atoms {
+        let mut spec = parse_atom(value);
+        if matches!(spec.curvature, CurvatureSpec::Unknown) {
+            spec.curvature = CurvatureSpec::Simple("affine".to_string());
+        }
+        insert(name, spec, "affine")?;
+    }
+
+    // Conditional rules are kept so parameterized atoms can resolve their curvature
+    for (name, value) in data.convex_atoms {
+        let mut spec = parse_atom(value);
+        if !matches!(spec.curvature, CurvatureSpec::Complex(_)) {
+            spec.curvature = CurvatureSpec::Simple("convex".to_string());
+        }
+        insert(name, spec, "convex")?;
+    }
+
+    for (name, value) in data.concave_atoms {
+        let mut spec = parse_atom(value);
+        if !matches!(spec.curvature, CurvatureSpec::Complex(_)) {
+            spec.curvature = CurvatureSpec::Simple("concave".to_string());
+        }
+        insert(name, spec, "concave")?;
+    }
+
+    Ok(specs)
+}
+
+/// Labels used for the log_loss test expression over the length-5 variable
+#[cfg(feature = "log-loss")]
+const LOG_LOSS_LABELS: [f64; 5] = [0.0, 1.0, 1.0, 0.0, 1.0];
+
+/// Huber threshold for the test expression; atoms.yaml test vectors assume M = 1
+const HUBER_M: f64 = 1.0;
+
+/// Exponent of the power test expression (sqrt equivalent)
+const POWER_TEST_P: f64 = 0.5;
+
+/// Parameter values the built-in test expression for `atom_name` uses
+fn test_expr_params(atom_name: &str) -> BTreeMap<String, f64> {
+    match atom_name {
+        "power" => BTreeMap::from([("p".to_string(), POWER_TEST_P)]),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// A variable with the same shape as `x`
+fn variable_like(x: &Expr) -> Expr {
+    match expr_dims(x).as_slice() {
+        [r, c] => variable([*r, *c]),
+        dims => variable(dims.iter().product::<usize>()),
+    }
+}
+
+/// Create a test expression for the given atom.
+///
+/// The atom is applied to `x`, and any further arguments take `x`'s shape, so
+/// the same atom can be built over scalar, vector and matrix inputs.
+fn create_test_expr(atom_name: &str, x: &Expr) -> Option<Expr> {
+    match atom_name {
+        // Affine atoms
+        "neg" => Some(negate(x)),
+        "sum" => Some(sum(x)),
+        "reshape" => Some(reshape(x, &[1, expr_dims(x).iter().product::<usize>()])),
+        "transpose" => Some(transpose(x)),
+        "trace" => {
+            let m = variable([3, 3]);
+            Some(trace(&m))
+        }
+        "diag" => Some(diag(x)),
+        "vstack" => Some(vstack(vec![x.clone(), variable_like(x)])),
+        "hstack" => Some(hstack(vec![x.clone(), variable_like(x)])),
+
+        // Convex atoms
+        "norm1" => Some(norm1(x)),
+        "norm2" => Some(norm2(x)),
+        "normInf" => Some(norm_inf(x)),
+        "abs" => Some(abs(x)),
+        "pos" => Some(pos(x)),
+        "negPart" => Some(neg_part(x)),
+        "maximum" => Some(max2(x, &variable_like(x))),
+        "sum_squares" | "sumSquares" => Some(sum_squares(x)),
+        "quad_form" | "quadForm" => {
+            let p = constant(nalgebra::DMatrix::identity(5, 5));
+            Some(quad_form(x, &p))
+        }
+        "quad_over_lin" | "quadOverLin" => Some(quad_over_lin(x, &vector_constant(&[2.0]))),
+        "exp" => Some(exp(x)),
+        #[cfg(feature = "sigma-max")]
+        "sigma_max" => {
+            let m = variable([3, 3]);
+            Some(sigma_max(&m))
+        }
+        #[cfg(feature = "berhu")]
+        "berhu" => Some(berhu(x, 1.0)),
+        #[cfg(feature = "log-loss")]
+        "log_loss" => Some(log_loss(x, &LOG_LOSS_LABELS)),
+        // Only curvature, sign and shape are checked here; folding the indicator
+        // into a constraint is cvxrust's canonicalizer's job and is tested there
+        #[cfg(feature = "indicator")]
+        "indicator" => Some(indicator(&x.le(&vector_constant(&[1.0; 5])))),
+        "max" => Some(max(x)),
+        "log_sum_exp" => Some(log_sum_exp(x)),
+        "huber" => Some(huber(x, HUBER_M)),
+        "inv_pos" => Some(inv_pos(x)),
+
+        // Concave atoms
+        "log" => Some(log(x)),
+        #[cfg(feature = "log1p")]
+        "log1p" => Some(log1p(x)),
+        "entropy" => Some(entropy(x)),
+        "sqrt" => Some(sqrt(x)),
+        "minimum" => Some(min2(x, &variable_like(x))),
+        "power" => Some(power(x, POWER_TEST_P)), // other exponents via instantiations
+        "min" => Some(min(x)),
+        "geo_mean" => Some(geo_mean(x)),
+        #[cfg(feature = "von-neumann-entropy")]
+        "von_neumann_entropy" => {
+            let m = variable([3, 3]);
+            Some(von_neumann_entropy(&m))
+        }
+
+        _ => None,
+    }
+}
+
+/// Builds an atom's test expression over `x`, or `None` if it can't
+pub type AtomConstructor = fn(&Expr) -> Option<Expr>;
+
+/// Builds a parameterized atom over `x` with the given parameter values, or `None` if it can't
+pub type AtomParamConstructor = fn(&Expr, &BTreeMap<String, f64>) -> Option<Expr>;
+
+/// Test-expression constructors keyed by spec atom name.
+///
+/// Atoms without a registered constructor fall back to the built-in ones, so
+/// downstream crates only register the atoms they add or want to override.
+#[derive(Debug, Default)]
+pub struct AtomRegistry {
+    constructors: HashMap<String, AtomConstructor>,
+    param_constructors: HashMap<String, AtomParamConstructor>,
+}
+
+impl AtomRegistry {
+    /// Register `build` for `atom_name`, replacing any built-in constructor
+    pub fn register(&mut self, atom_name: &str, build: AtomConstructor) {
+        self.constructors.insert(atom_name.to_string(), build);
+    }
+
+    /// Build the test expression for `atom_name` over `x`
+    pub fn build(&self, atom_name: &str, x: &Expr) -> Option<Expr> {
+        match self.constructors.get(atom_name) {
+            Some(build) => build(x),
+            None => create_test_expr(atom_name, x),
+        }
+    }
+
+    /// Register `build` for the instantiations of `atom_name`, replacing any built-in one
+    pub fn register_param(&mut self, atom_name: &str, build: AtomParamConstructor) {
+        self.param_constructors.insert(atom_name.to_string(), build);
+    }
+
+    /// Build `atom_name` over `x` at one parameter instantiation
+    pub fn build_param(
+        &self,
+        atom_name: &str,
+        x: &Expr,
+        params: &BTreeMap<String, f64>,
+    ) -> Option<Expr> {
+        match self.param_constructors.get(atom_name) {
+            Some(build) => build(x, params),
+            None => create_param_expr(atom_name, x, params),
+        }
+    }
+}
+
+/// Build a parameterized atom over `x` with the given parameter values
+fn create_param_expr(atom_name: &str, x: &Expr, params: &BTreeMap<String, f64>) -> Option<Expr> {
+    match atom_name {
+        "power" => Some(power(x, *params.get("p")?)),
+        #[cfg(feature = "berhu")]
+        "berhu" => Some(berhu(x, *params.get("M")?)),
+        _ => None,
+    }
+}
+
+thread_local! {
+    static EXPECTING_PANIC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Install a panic hook that stays quiet while `try_build` is probing for a rejection.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !EXPECTING_PANIC.with(Cell::get) {
+                default_hook(info);
+            }
+        }));
+    });
+}
+
+/// Run a cvxrust call, turning a panic inside it into an error message
+fn try_build<T, F: FnOnce() -> T>(build: F) -> Result<T, String> {
+    install_panic_hook();
+    // Restore the previous flag so nested calls keep the outer one quiet
+    let outer = EXPECTING_PANIC.with(|flag| flag.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(build));
+    EXPECTING_PANIC.with(|flag| flag.set(outer));
+
+    result.map_err(|payload| {
+        if let Some(msg) = payload.downcast_ref::<&str>() {
+            msg.to_string()
+        } else if let Some(msg) = payload.downcast_ref::<String>() {
+            msg.clone()
+        } else {
+            "panicked".to_string()
+        }
+    })
+}
+
+/// Build a column-vector constant expression
+fn vector_constant(values: &[f64]) -> Expr {
+    constant(DMatrix::from_column_slice(values.len(), 1, values))
+}
+
+/// Negation, the spec's affine `neg` atom
+fn negate(x: &Expr) -> Expr {
+    -x.clone()
+}
+
+/// A length-n variable with a nonnegativity attribute
+#[cfg(feature = "nonneg-variables")]
+fn nonneg_variable(n: usize) -> Expr {
+    VariableBuilder::vector(n).nonneg().build()
+}
+
+/// Without sign-tagged variables, a plain length-n variable
+#[cfg(not(feature = "nonneg-variables"))]
+fn nonneg_variable(n: usize) -> Expr {
+    variable(n)
+}
+
+/// Numerically evaluate an expression built only from constants
+#[cfg(feature = "expr-value")]
+fn evaluate(expr: &Expr) -> Option<DMatrix<f64>> {
+    expr.value()
+}
+
+/// Without `Expr::value` nothing evaluates; the numeric checks are skipped
+#[cfg(not(feature = "expr-value"))]
+fn evaluate(_expr: &Expr) -> Option<DMatrix<f64>> {
+    None
+}
+
+/// Gradient of a scalar expression with respect to `x` at `point`
+#[cfg(feature = "expr-gradient")]
+fn gradient(expr: &Expr, x: &Expr, point: &[f64]) -> Option<Vec<f64>> {
+    let at = DMatrix::from_column_slice(point.len(), 1, point);
+    expr.gradient(x, &at).map(|g| g.as_slice().to_vec())
+}
+
+/// Without `Expr::gradient` there is no analytic gradient; the gradient check is skipped
+#[cfg(not(feature = "expr-gradient"))]
+fn gradient(_expr: &Expr, _x: &Expr, _point: &[f64]) -> Option<Vec<f64>> {
+    None
+}
+
+/// Check that an atom constructor rejects an invalid parameter value
+#[cfg(any(feature = "berhu", feature = "log-loss"))]
+fn check_rejects_parameter<F: FnOnce() -> Expr>(param: &str, build: F) -> ValidationCheck {
+    let (passed, message) = match try_build(build) {
+        Ok(_) => (false, format!("accepted invalid {}", param)),
+        Err(e) => (true, format!("rejected invalid {}: {}", param, e)),
+    };
+
+    ValidationCheck {
+        name: "parameter_domain".to_string(),
+        passed,
+        message,
+    }
+}
+
+/// Check convexity or concavity numerically between two sample points.
+///
+/// For every lambda in (0, 1) a convex atom must satisfy
+/// f(l*a + (1-l)*b) <= l*f(a) + (1-l)*f(b) elementwise; a concave atom must
+/// satisfy the reverse inequality. `build` may fail, e.g. when the atom has no
+/// constructor, which counts as an evaluation failure.
+fn check_numeric_curvature<F: Fn(&Expr) -> Option<Expr>>(
+    build: F,
+    a: &DMatrix<f64>,
+    b: &DMatrix<f64>,
+    expected: &str,
+    tol: f64,
+) -> ValidationCheck {
+    let concave = expected == "concave";
+    let eval_at = |point: &DMatrix<f64>| evaluate(&build(&constant(point.clone()))?);
+
+    let (fa, fb) = match (eval_at(a), eval_at(b)) {
+        (Some(fa), Some(fb)) => (fa, fb),
+        _ => {
+            return ValidationCheck {
+                name: "numeric_curvature".to_string(),
+                passed: false,
+                message: "could not evaluate atom at sample points".to_string(),
+            }
+        }
+    };
+
+    for lambda in [0.25, 0.5, 0.75] {
+        let mid = a * lambda + b * (1.0 - lambda);
+        let Some(f_mid) = eval_at(&mid) else {
+            return ValidationCheck {
+                name: "numeric_curvature".to_string(),
+                passed: false,
+                message: format!("could not evaluate atom at lambda = {}", lambda),
+            };
+        };
+        let chord = &fa * lambda + &fb * (1.0 - lambda);
+        for (value, secant) in f_mid.iter().zip(chord.iter()) {
+            let violated = if concave {
+                *value < *secant - tol
+            } else {
+                *value > *secant + tol
+            };
+            if violated {
+                return ValidationCheck {
+                    name: "numeric_curvature".to_string(),
+                    passed: false,
+                    message: format!(
+                        "{} violated at lambda = {}: f(mid) = {}, chord = {}",
+                        if concave { "concavity" } else { "convexity" },
+                        lambda,
+                        value,
+                        secant
+                    ),
+                };
+            }
+        }
+    }
+
+    ValidationCheck {
+        name: "numeric_curvature".to_string(),
+        passed: true,
+        message: format!("{} holds at all sample points", expected),
+    }
+}
+
+/// Dimensions of an expression's shape
+fn expr_dims(expr: &Expr) -> Vec<usize> {
+    expr.shape().dims().to_vec()
+}
+
+/// Check an elementwise atom on a 3x3 matrix: shape is preserved and the
+/// spec's curvature and sign still hold.
+fn check_matrix_elementwise<F: Fn(&Expr) -> Expr>(
+    build: F,
+    expected_curv: &str,
+    expected_sign: &str,
+) -> ValidationCheck {
+    let m = variable([3, 3]);
+    let expr = build(&m);
+
+    let dims = expr_dims(&expr);
+    let curv = check_curvature(&expr, expected_curv);
+    let sign = check_sign(&expr, expected_sign);
+
+    let (passed, message) = if dims != [3, 3] {
+        (false, format!("expected shape [3, 3], got {:?}", dims))
+    } else if !curv.passed {
+        (false, format!("curvature {}", curv.message))
+    } else if !sign.passed {
+        (false, format!("sign {}", sign.message))
+    } else {
+        (true, "3x3 input keeps shape, curvature and sign".to_string())
+    };
+
+    ValidationCheck {
+        name: "matrix_input".to_string(),
+        passed,
+        message,
+    }
+}
+
+/// Check norm2 on a 3x3 matrix: a convex, nonnegative scalar equal to the Frobenius norm
+fn check_frobenius_norm(tol: f64) -> ValidationCheck {
+    let m = variable([3, 3]);
+    let expr = norm2(&m);
+    let dims = matrix_dims(&expr);
+    let curv = check_curvature(&expr, "convex");
+    let sign = check_sign(&expr, "nonnegative");
+
+    let data = DMatrix::from_row_slice(3, 3, &[1.0, -2.0, 0.0, 3.0, 0.5, -1.0, 2.0, 0.0, 4.0]);
+    let value = check_numeric_value(&norm2(&constant(data.clone())), &[data.norm()], tol);
+
+    let (passed, message) = if dims != (1, 1) {
+        (false, format!("expected scalar result, got shape {:?}", dims))
+    } else if !curv.passed {
+        (false, format!("curvature {}", curv.message))
+    } else if !sign.passed {
+        (false, format!("sign {}", sign.message))
+    } else if !value.passed {
+        (false, format!("Frobenius norm {}", value.message))
+    } else {
+        (true, "3x3 input gives the Frobenius norm".to_string())
+    };
+
+    ValidationCheck {
+        name: "matrix_input".to_string(),
+        passed,
+        message,
+    }
+}
+
+/// Check that a constant expression evaluates to the expected values
+fn check_numeric_value(expr: &Expr, expected: &[f64], tol: f64) -> ValidationCheck {
+    let (passed, message) = match evaluate(expr) {
+        Some(actual) => {
+            let max_err = actual
+                .iter()
+                .zip(expected)
+                .map(|(a, e)| (a - e).abs())
+                .fold(0.0, f64::max);
+            if actual.len() == expected.len() && max_err <= tol {
+                (true, format!("value matches within {:e}", tol))
+            } else {
+                (
+                    false,
+                    format!("expected {:?}, got {:?}", expected, actual.as_slice()),
+                )
+            }
+        }
+        None => (false, format!("expected {:?}, could not evaluate", expected)),
+    };
+
+    ValidationCheck {
+        name: "numeric".to_string(),
+        passed,
+        message,
+    }
+}
+
+/// Check a spec test vector by evaluating the atom over a constant input
+fn check_numeric(
+    atom_name: &str,
+    spec: &AtomSpec,
+    vector: &TestVector,
+    options: &ValidationOptions,
+) -> ValidationCheck {
+    let expected = vector.expected.to_vec();
+    let tol = spec.tol(options.tol);
+    let mut check = match options.registry.build(atom_name, &vector_constant(&vector.input)) {
+        Some(expr) => check_numeric_value(&expr, &expected, tol),
+        None => ValidationCheck {
+            name: "numeric".to_string(),
+            passed: false,
+            message: format!("expected {:?}, could not build the atom", expected),
+        },
+    };
+    check.message = format!("input {:?}: {}", vector.input, check.message);
+    if options.verbose {
+        check.message = format!("{} (tol {:e})", check.message, tol);
+    }
+    check
+}
+
+/// Check that evaluating an atom outside its domain yields no finite value
+#[cfg(any(feature = "log1p", feature = "von-neumann-entropy"))]
+fn check_outside_domain<F: FnOnce(&Expr) -> Expr>(
+    build: F,
+    point: &DMatrix<f64>,
+    domain: &str,
+) -> ValidationCheck {
+    let outcome = try_build(|| evaluate(&build(&constant(point.clone()))));
+    let (passed, message) = match outcome {
+        Err(e) => (true, format!("rejected point outside {} domain: {}", domain, e)),
+        Ok(None) => (true, format!("no value outside {} domain", domain)),
+        Ok(Some(v)) if v.iter().any(|x| !x.is_finite()) => {
+            (true, format!("non-finite value outside {} domain", domain))
+        }
+        Ok(Some(v)) => (
+            false,
+            format!("returned finite value {:?} outside {} domain", v.as_slice(), domain),
+        ),
+    };
+
+    ValidationCheck {
+        name: "domain".to_string(),
+        passed,
+        message,
+    }
+}
+
+/// Check that reshape round-trips preserve values and their order.
+///
+/// Covers vec/mat (flattening a matrix to a column and back) and that chained
+/// reshapes agree with a single reshape to the final shape.
+fn check_shape_roundtrip(tol: f64) -> ValidationCheck {
+    let data = DMatrix::from_column_slice(2, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    let x = constant(data.clone());
+    let shapes: [[usize; 2]; 4] = [[3, 2], [6, 1], [1, 6], [2, 3]];
+
+    let mut cases: Vec<(String, Expr, Expr)> = vec![(
+        "mat(vec(X)) == X".to_string(),
+        reshape(&reshape(&x, &[6, 1]), &[2, 3]),
+        x.clone(),
+    )];
+    for s1 in &shapes {
+        for s2 in &shapes {
+            cases.push((
+                format!("reshape(reshape(X, {:?}), {:?}) == reshape(X, {:?})", s1, s2, s2),
+                reshape(&reshape(&x, s1), s2),
+                reshape(&x, s2),
+            ));
+        }
+    }
+
+    for (label, lhs, rhs) in &cases {
+        match (evaluate(lhs), evaluate(rhs)) {
+            (Some(l), Some(r)) if l.shape() == r.shape() && (&l - &r).amax() <= tol => {}
+            (Some(l), Some(r)) => {
+                return ValidationCheck {
+                    name: "shape_roundtrip".to_string(),
+                    passed: false,
+                    message: format!(
+                        "{} failed: got {:?} vs {:?}",
+                        label,
+                        l.as_slice(),
+                        r.as_slice()
+                    ),
+                }
+            }
+            _ => {
+                return ValidationCheck {
+                    name: "shape_roundtrip".to_string(),
+                    passed: false,
+                    message: format!("{}: could not evaluate", label),
+                }
+            }
+        }
+    }
+
+    ValidationCheck {
+        name: "shape_roundtrip".to_string(),
+        passed: true,
+        message: format!("{} reshape identities hold", cases.len()),
+    }
+}
+
+/// Check that reshape preserves the element count.
+///
+/// Reshaping the length-5 variable to [5, 1] and [1, 5] must succeed with those
+/// dimensions and [2, 3] (6 elements) must be rejected.
+fn check_reshape_elements() -> ValidationCheck {
+    let fail = |message: String| ValidationCheck {
+        name: "reshape_elements".to_string(),
+        passed: false,
+        message,
+    };
+
+    let x = variable(5);
+    for target in [[5, 1], [1, 5]] {
+        match try_build(|| reshape(&x, &target)) {
+            Ok(expr) if matrix_dims(&expr) == (target[0], target[1]) => {}
+            Ok(expr) => {
+                return fail(format!(
+                    "reshape to {:?} gave shape {:?}",
+                    target,
+                    matrix_dims(&expr)
+                ))
+            }
+            Err(e) => return fail(format!("rejected reshape of 5 elements to {:?}: {}", target, e)),
+        }
+    }
+    if let Ok(expr) = try_build(|| reshape(&x, &[2, 3])) {
+        return fail(format!(
+            "accepted reshape of 5 elements to [2, 3], got shape {:?}",
+            matrix_dims(&expr)
+        ));
+    }
+
+    ValidationCheck {
+        name: "reshape_elements".to_string(),
+        passed: true,
+        message: "element count preserved; mismatched count rejected".to_string(),
+    }
+}
+
+/// Reference reshape of `m` to `rows x cols`: entries are read down the
+/// columns of `m` and written down the columns of the result.
+fn column_major_reshape(m: &DMatrix<f64>, rows: usize, cols: usize) -> DMatrix<f64> {
+    DMatrix::from_fn(rows, cols, |i, j| {
+        let k = i + j * rows;
+        m[(k % m.nrows(), k / m.nrows())]
+    })
+}
+
+/// Check that reshape matches nalgebra's column-major layout, the order
+/// cones.yaml uses for vec, and that reshaping back recovers the original.
+fn check_reshape_layout(tol: f64) -> ValidationCheck {
+    let fail = |message: String| ValidationCheck {
+        name: "reshape_layout".to_string(),
+        passed: false,
+        message,
+    };
+
+    let data = DMatrix::from_column_slice(2, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    let reshaped = reshape(&constant(data.clone()), &[3, 2]);
+    let expected = column_major_reshape(&data, 3, 2);
+    match evaluate(&reshaped) {
+        Some(v) if v.shape() == expected.shape() && (&v - &expected).amax() <= tol => {}
+        Some(v) => {
+            return fail(format!(
+                "reshape of {} to [3, 2] gave {}, expected column-major {}",
+                format_matrix(&data),
+                format_matrix(&v),
+                format_matrix(&expected)
+            ))
+        }
+        None => return fail("could not evaluate reshape to [3, 2]".to_string()),
+    }
+    match evaluate(&reshape(&reshaped, &[2, 3])) {
+        Some(v) if v.shape() == data.shape() && (&v - &data).amax() <= tol => {}
+        Some(v) => {
+            return fail(format!(
+                "reshaping back gave {}, expected {}",
+                format_matrix(&v),
+                format_matrix(&data)
+            ))
+        }
+        None => return fail("could not evaluate reshape back to [2, 3]".to_string()),
+    }
+
+    ValidationCheck {
+        name: "reshape_layout".to_string(),
+        passed: true,
+        message: "column-major round-trip".to_string(),
+    }
+}
+
+/// Fixed seed for the linearity samples so a failure can be reproduced
+const LINEARITY_SEED: u64 = 0x11ea_7e57;
+
+/// Number of random (x, y, a, b) samples per affine atom
+const LINEARITY_SAMPLES: usize = 10;
+
+/// An affine atom as a linear map, with a reference implementation on matrices
+struct LinearMap {
+    /// Shape of the sampled argument
+    arg_shape: (usize, usize),
+    build: fn(&Expr) -> Expr,
+    reference: fn(&DMatrix<f64>) -> DMatrix<f64>,
+}
+
+fn linear_map(atom_name: &str) -> Option<LinearMap> {
+    let map = match atom_name {
+        "sum" => LinearMap {
+            arg_shape: (5, 1),
+            build: |x| sum(x),
+            reference: |m| DMatrix::from_element(1, 1, m.sum()),
+        },
+        "reshape" => LinearMap {
+            arg_shape: (2, 3),
+            build: |x| reshape(x, &[3, 2]),
+            reference: |m| DMatrix::from_column_slice(3, 2, m.as_slice()),
+        },
+        "transpose" => LinearMap {
+            arg_shape: (2, 3),
+            build: |x| transpose(x),
+            reference: |m| m.transpose(),
+        },
+        "trace" => LinearMap {
+            arg_shape: (3, 3),
+            build: |x| trace(x),
+            reference: |m| DMatrix::from_element(1, 1, m.trace()),
+        },
+        "diag" => LinearMap {
+            arg_shape: (4, 1),
+            build: |x| diag(x),
+            reference: |m| DMatrix::from_diagonal(&m.column(0).clone_owned()),
+        },
+        "vstack" => LinearMap {
+            arg_shape: (2, 3),
+            build: |x| vstack(vec![x.clone(), x.clone()]),
+            reference: |m| {
+                let mut out = DMatrix::zeros(2 * m.nrows(), m.ncols());
+                out.rows_mut(0, m.nrows()).copy_from(m);
+                out.rows_mut(m.nrows(), m.nrows()).copy_from(m);
+                out
+            },
+        },
+        "hstack" => LinearMap {
+            arg_shape: (2, 3),
+            build: |x| hstack(vec![x.clone(), x.clone()]),
+            reference: |m| {
+                let mut out = DMatrix::zeros(m.nrows(), 2 * m.ncols());
+                out.columns_mut(0, m.ncols()).copy_from(m);
+                out.columns_mut(m.ncols(), m.ncols()).copy_from(m);
+                out
+            },
+        },
+        _ => return None,
+    };
+    Some(map)
+}
+
+/// Whether two matrices have the same shape and agree to a relative `tol`
+fn matrices_close(a: &DMatrix<f64>, b: &DMatrix<f64>, tol: f64) -> bool {
+    a.shape() == b.shape() && (a - b).amax() <= tol * b.amax().max(1.0)
+}
+
+/// Check that an affine atom computes the right linear map.
+///
+/// On random constants x, y and scalars a, b, f(a*x + b*y) must equal
+/// a*f(x) + b*f(y), and f(x) must match a reference implementation of the
+/// map. Returns `None` for atoms without a linear map here.
+fn check_linearity(atom_name: &str, tol: f64) -> Option<ValidationCheck> {
+    let map = linear_map(atom_name)?;
+    let mut rng = StdRng::seed_from_u64(LINEARITY_SEED);
+    let (rows, cols) = map.arg_shape;
+    let fail = |message: String| ValidationCheck {
+        name: "linearity".to_string(),
+        passed: false,
+        message,
+    };
+
+    for _ in 0..LINEARITY_SAMPLES {
+        let x = DMatrix::from_fn(rows, cols, |_, _| rng.gen_range(-3.0..3.0));
+        let y = DMatrix::from_fn(rows, cols, |_, _| rng.gen_range(-3.0..3.0));
+        let (a, b): (f64, f64) = (rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0));
+
+        let eval_at = |m: &DMatrix<f64>| evaluate(&(map.build)(&constant(m.clone())));
+        let combined = eval_at(&(&x * a + &y * b));
+        let (Some(fx), Some(fy), Some(combined)) = (eval_at(&x), eval_at(&y), combined) else {
+            return Some(fail("could not evaluate atom at sample points".to_string()));
+        };
+
+        let superposed = &fx * a + &fy * b;
+        if !matrices_close(&combined, &superposed, tol) {
+            return Some(fail(format!(
+                "f(a*x + b*y) = {:?}, a*f(x) + b*f(y) = {:?} (a = {}, b = {})",
+                combined.as_slice(),
+                superposed.as_slice(),
+                a,
+                b
+            )));
+        }
+
+        let expected = (map.reference)(&x);
+        if !matrices_close(&fx, &expected, tol) {
+            return Some(fail(format!(
+                "f(x) = {:?} with shape {:?}, expected {:?} with shape {:?}",
+                fx.as_slice(),
+                fx.shape(),
+                expected.as_slice(),
+                expected.shape()
+            )));
+        }
+    }
+
+    Some(ValidationCheck {
+        name: "linearity".to_string(),
+        passed: true,
+        message: format!("superposition and reference map hold on {} samples", LINEARITY_SAMPLES),
+    })
+}
+
+/// Row-major rendering of a matrix for failure messages
+fn format_matrix(m: &DMatrix<f64>) -> String {
+    let rows: Vec<String> = m
+        .row_iter()
+        .map(|row| format!("{:?}", row.iter().collect::<Vec<_>>()))
+        .collect();
+    format!("[{}]", rows.join(", "))
+}
+
+/// Check quad_form's curvature against the definiteness of P.
+///
+/// A PSD P must give convex. A negative definite P may be rejected or give
+/// concave, and a non-symmetric P may be rejected or give unknown.
+fn check_quad_form_psd() -> ValidationCheck {
+    let n = 5;
+    let negative_definite = DMatrix::from_fn(n, n, |i, j| match i.abs_diff(j) {
+        0 => -2.0,
+        1 => 0.5,
+        _ => 0.0,
+    });
+    let non_symmetric =
+        DMatrix::from_fn(n, n, |i, j| if i == j || j == i + 1 { 1.0 } else { 0.0 });
+    let cases = [
+        ("identity", DMatrix::identity(n, n), "convex"),
+        ("negative definite", negative_definite, "concave"),
+        ("non-symmetric", non_symmetric, "unknown"),
+    ];
+
+    let x = variable(n);
+    for (label, p, expected) in &cases {
+        let outcome = try_build(|| quad_form(&x, &constant(p.clone())).curvature());
+        let got = match &outcome {
+            Ok(curv) => curvature_name(curv).to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+        // Rejecting P is only wrong for the PSD case
+        let passed = match &outcome {
+            Ok(curv) => curvature_name(curv) == *expected,
+            Err(_) => *expected != "convex",
+        };
+        if !passed {
+            return ValidationCheck {
+                name: "quad_form_psd".to_string(),
+                passed: false,
+                message: format!(
+                    "{} P = {}: expected {}, got {}",
+                    label,
+                    format_matrix(p),
+                    expected,
+                    got
+                ),
+            };
+        }
+    }
+
+    ValidationCheck {
+        name: "quad_form_psd".to_string(),
+        passed: true,
+        message: "curvature follows the definiteness of P".to_string(),
+    }
+}
+
+/// Check quad_over_lin against its closed form as the perspective of sum_squares.
+///
+/// The perspective of f is s * f(x / s) for s > 0, so quad_over_lin(x, s) must
+/// equal s * sum_squares(x / s) and share its convex curvature.
+fn check_perspective(tol: f64) -> ValidationCheck {
+    let x = DMatrix::from_column_slice(5, 1, &[1.0, -2.0, 0.5, 3.0, -1.5]);
+
+    for s in [0.5, 1.0, 4.0] {
+        let qol = evaluate(&quad_over_lin(&constant(x.clone()), &vector_constant(&[s])));
+        let persp = evaluate(&sum_squares(&constant(&x / s))).map(|v| v * s);
+        match (qol, persp) {
+            (Some(q), Some(p)) if (q[0] - p[0]).abs() <= tol * p[0].abs().max(1.0) => {}
+            (Some(q), Some(p)) => {
+                return ValidationCheck {
+                    name: "perspective".to_string(),
+                    passed: false,
+                    message: format!(
+                        "s = {}: quad_over_lin = {}, s * sum_squares(x / s) = {}",
+                        s, q[0], p[0]
+                    ),
+                }
+            }
+            _ => {
+                return ValidationCheck {
+                    name: "perspective".to_string(),
+                    passed: false,
+                    message: format!("s = {}: could not evaluate", s),
+                }
+            }
+        }
+    }
+
+    let v = variable(5);
+    let curv = check_curvature(&quad_over_lin(&v, &vector_constant(&[2.0])), "convex");
+    ValidationCheck {
+        name: "perspective".to_string(),
+        passed: curv.passed,
+        message: if curv.passed {
+            "matches s * sum_squares(x / s) and is convex".to_string()
+        } else {
+            format!("perspective curvature {}", curv.message)
+        },
+    }
+}
+
+/// Input shapes each atom is tried on by `check_shape`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TestShape {
+    Scalar,
+    Vector,
+    Matrix,
+}
+
+impl TestShape {
+    const ALL: [TestShape; 3] = [TestShape::Scalar, TestShape::Vector, TestShape::Matrix];
+
+    /// A variable of this shape, nonnegative when `nonneg` is set
+    fn variable(self, nonneg: bool) -> Expr {
+        match (self, nonneg) {
+            (TestShape::Scalar, false) => variable(1),
+            (TestShape::Vector, false) => variable(5),
+            (TestShape::Matrix, false) => variable([3, 3]),
+            (TestShape::Scalar, true) => nonneg_variable(1),
+            (TestShape::Vector, true) => nonneg_variable(5),
+            (TestShape::Matrix, true) => reshape(&nonneg_variable(9), &[3, 3]),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            TestShape::Scalar => "scalar",
+            TestShape::Vector => "vector",
+            TestShape::Matrix => "3x3 matrix",
+        }
+    }
+}
+
+/// Expected output (rows, cols) of an atom over an input of shape `(r, c)`.
+///
+/// Returns `None` where the atom has no meaningful result for that input,
+/// e.g. quad_form's fixed 5x5 P or a vector norm of a matrix.
+fn expected_shape(
+    atom_name: &str,
+    shape: TestShape,
+    (r, c): (usize, usize),
+) -> Option<(usize, usize)> {
+    let matrix = shape == TestShape::Matrix;
+    match atom_name {
+        "sum" | "sumSquares" | "quadOverLin" | "norm2" | "max" | "min" | "log_sum_exp" => {
+            Some((1, 1))
+        }
+        "norm1" | "normInf" | "geo_mean" if !matrix => Some((1, 1)),
+        "neg" | "abs" | "pos" | "negPart" | "exp" | "log" | "log1p" | "sqrt" | "entropy" | "power"
+        | "berhu" | "maximum" | "minimum" | "huber" | "inv_pos" => Some((r, c)),
+        "transpose" => Some((c, r)),
+        "reshape" => Some((1, r * c)),
+        "vstack" => Some((2 * r, c)),
+        "hstack" => Some((r, 2 * c)),
+        "diag" => match shape {
+            TestShape::Vector => Some((r, r)),
+            TestShape::Matrix => Some((r.min(c), 1)),
+            TestShape::Scalar => None,
+        },
+        "trace" | "sigma_max" | "von_neumann_entropy" if matrix => Some((1, 1)),
+        "quadForm" | "log_loss" | "indicator" if shape == TestShape::Vector => Some((1, 1)),
+        _ => None,
+    }
+}
+
+/// Check the atom's output shape over scalar, vector and matrix inputs.
+///
+/// Atoms whose dcp_requires restricts the argument's sign are built over
+/// nonnegative inputs, as in `validate_atom`. Shapes the atom isn't
+/// meaningful for are skipped; returns `None` if none apply.
+fn check_shape(
+    atom_name: &str,
+    spec: &AtomSpec,
+    registry: &AtomRegistry,
+) -> Option<ValidationCheck> {
+    let nonneg = requires_positive_arg(spec);
+    let mut tested = Vec::new();
+
+    for shape in TestShape::ALL {
+        let x = shape.variable(nonneg);
+        let Some(expected) = expected_shape(atom_name, shape, matrix_dims(&x)) else {
+            continue;
+        };
+        let actual = match try_build(|| registry.build(atom_name, &x)) {
+            Ok(Some(expr)) => matrix_dims(&expr),
+            Ok(None) => return None,
+            Err(e) => {
+                return Some(ValidationCheck {
+                    name: "shape".to_string(),
+                    passed: false,
+                    message: format!("{} input rejected: {}", shape.name(), e),
+                })
+            }
+        };
+        if actual != expected {
+            return Some(ValidationCheck {
+                name: "shape".to_string(),
+                passed: false,
+                message: format!(
+                    "{} input: expected shape {:?}, got {:?}",
+                    shape.name(),
+                    expected,
+                    actual
+                ),
+            });
+        }
+        tested.push(shape.name());
+    }
+
+    if tested.is_empty() {
+        return None;
+    }
+    Some(ValidationCheck {
+        name: "shape".to_string(),
+        passed: true,
+        message: format!("output shape correct for {} input", tested.join(", ")),
+    })
+}
+
+/// An expression's dimensions as (rows, cols), treating a 1-D shape as a column
+fn matrix_dims(expr: &Expr) -> (usize, usize) {
+    match expr_dims(expr).as_slice() {
+        [] => (1, 1),
+        [n] => (*n, 1),
+        [r, c, ..] => (*r, *c),
+    }
+}
+
+/// Build a variadic atom over `args` with cvxrust's slice-taking constructor.
+///
+/// cvxrust's maximum and minimum are the binary max2/min2, so their argument
+/// count is fixed by the signature and there is nothing to build here.
+fn build_variadic(atom_name: &str, args: &[Expr]) -> Option<Expr> {
+    match atom_name {
+        "vstack" => Some(vstack(args.to_vec())),
+        "hstack" => Some(hstack(args.to_vec())),
+        _ => None,
+    }
+}
+
+/// Check a variadic atom over 1 and 3 arguments, and that stacking nothing is
+/// rejected. Returns `None` for atoms without a slice-taking constructor.
+fn check_variadic(atom_name: &str) -> Option<ValidationCheck> {
+    let fail = |message: String| ValidationCheck {
+        name: "arity".to_string(),
+        passed: false,
+        message,
+    };
+
+    if !matches!(atom_name, "vstack" | "hstack") {
+        return None;
+    }
+
+    for count in [1, 3] {
+        let args: Vec<Expr> = (0..count).map(|_| variable(5)).collect();
+        let (rows, cols) = matrix_dims(&args[0]);
+        let expected = match atom_name {
+            "vstack" => (rows * count, cols),
+            _ => (rows, cols * count),
+        };
+        match try_build(|| build_variadic(atom_name, &args)) {
+            Ok(Some(expr)) if matrix_dims(&expr) == expected => {}
+            Ok(Some(expr)) => {
+                return Some(fail(format!(
+                    "{} arguments: expected shape {:?}, got {:?}",
+                    count,
+                    expected,
+                    matrix_dims(&expr)
+                )))
+            }
+            Ok(None) => return None,
+            Err(e) => return Some(fail(format!("rejected {} arguments: {}", count, e))),
+        }
+    }
+
+    if let Ok(Some(expr)) = try_build(|| build_variadic(atom_name, &[])) {
+        return Some(fail(format!("accepted 0 arguments, got shape {:?}", matrix_dims(&expr))));
+    }
+
+    Some(ValidationCheck {
+        name: "arity".to_string(),
+        passed: true,
+        message: "variadic: 1 and 3 arguments are well-formed, 0 rejected".to_string(),
+    })
+}
+
+/// Check stacked dimensions over mixed argument sizes, and that arguments
+/// whose inner dimension disagrees are rejected rather than stacked.
+fn check_stack_shapes(atom_name: &str) -> ValidationCheck {
+    let fail = |message: String| ValidationCheck {
+        name: "stack_shape".to_string(),
+        passed: false,
+        message,
+    };
+
+    // (arguments, expected shape) pairs, then a pair that must be rejected
+    let (cases, mismatched) = match atom_name {
+        "vstack" => (
+            vec![
+                (vec![variable(5), variable(5)], (10, 1)),
+                (vec![variable(5), variable(3)], (8, 1)),
+                (vec![variable([2, 3]), variable([4, 3])], (6, 3)),
+            ],
+            vec![variable([1, 5]), variable([1, 3])],
+        ),
+        _ => (
+            vec![
+                (vec![variable(3), variable(3)], (3, 2)),
+                (vec![variable([3, 2]), variable([3, 4])], (3, 6)),
+            ],
+            vec![variable(5), variable(3)],
+        ),
+    };
+
+    for (args, expected) in &cases {
+        let inputs: Vec<(usize, usize)> = args.iter().map(matrix_dims).collect();
+        match try_build(|| build_variadic(atom_name, args)) {
+            Ok(Some(expr)) if matrix_dims(&expr) == *expected => {}
+            Ok(Some(expr)) => {
+                return fail(format!(
+                    "inputs {:?}: expected shape {:?}, got {:?}",
+                    inputs,
+                    expected,
+                    matrix_dims(&expr)
+                ))
+            }
+            Ok(None) => return fail(format!("no stacking test for '{}'", atom_name)),
+            Err(e) => return fail(format!("rejected inputs {:?}: {}", inputs, e)),
+        }
+    }
+
+    let inputs: Vec<(usize, usize)> = mismatched.iter().map(matrix_dims).collect();
+    if let Ok(Some(expr)) = try_build(|| build_variadic(atom_name, &mismatched)) {
+        return fail(format!(
+            "accepted incompatible inputs {:?}, got shape {:?}",
+            inputs,
+            matrix_dims(&expr)
+        ));
+    }
+
+    ValidationCheck {
+        name: "stack_shape".to_string(),
+        passed: true,
+        message: format!("stacked shapes correct; rejected inputs {:?}", inputs),
+    }
+}
+
+/// Check an atom against the spec's `arity` and `shape`.
+///
+/// Unary and binary arities are fixed by cvxrust's constructor signatures and
+/// can't be violated, so for those we only check the declared result shape;
+/// variadic atoms are built with several argument counts. Returns `None` when
+/// there is nothing to check.
+fn check_arity(
+    atom_name: &str,
+    spec: &AtomSpec,
+    x: &Expr,
+    expr: &Expr,
+) -> Option<ValidationCheck> {
+    if spec.arity == "variadic" {
+        return check_variadic(atom_name);
+    }
+
+    let (passed, message) = match spec.shape.as_str() {
+        "same_as_arg" if spec.arity == "unary" => {
+            let (arg, out) = (matrix_dims(x), matrix_dims(expr));
+            if arg == out {
+                (true, format!("unary, elementwise over shape {:?}", arg))
+            } else {
+                (false, format!("elementwise atom changed shape {:?} to {:?}", arg, out))
+            }
+        }
+        "scalar" => match matrix_dims(expr) {
+            (1, 1) => (true, format!("{}, scalar result", spec.arity)),
+            out => (false, format!("expected scalar result, got shape {:?}", out)),
+        },
+        _ => return None,
+    };
+
+    Some(ValidationCheck {
+        name: "arity".to_string(),
+        passed,
+        message,
+    })
+}
+
+/// Whether the spec's `dcp_requires` asks for a positive or nonnegative test argument.
+///
+/// Only requirements naming the argument the test expression is built over
+/// count: "..._arg" for unary atoms and "..._x" for the first argument.
+/// quadOverLin's "concave_positive_y" restricts its other argument.
+fn requires_positive_arg(spec: &AtomSpec) -> bool {
+    spec.dcp_requires.as_deref().is_some_and(|requires| {
+        requires.split(", ").any(|r| {
+            (r.ends_with("_arg") || r.ends_with("_x"))
+                && (r.contains("positive") || r.contains("nonneg"))
+        })
+    })
+}
+
+/// A length-n test argument for the atom: nonnegative if its `dcp_requires`
+/// restricts the argument's sign, sign-unknown otherwise
+fn test_variable(spec: &AtomSpec, n: usize) -> Expr {
+    if requires_positive_arg(spec) {
+        nonneg_variable(n)
+    } else {
+        variable(n)
+    }
+}
+
+/// Check that a positivity-restricted atom refuses to classify a sign-unknown argument.
+///
+/// Building the atom over a sign-unknown variable must either fail with a DCP
+/// error or give unknown curvature, never the spec's curvature. Driven by
+/// `dcp_requires`; returns `None` for atoms without a positivity requirement,
+/// and without the `nonneg-variables` feature, since the atom's own curvature
+/// is then checked over a sign-unknown argument too.
+fn check_domain_violation(
+    atom_name: &str,
+    spec: &AtomSpec,
+    registry: &AtomRegistry,
+) -> Option<ValidationCheck> {
+    let requirement = spec.dcp_requires.as_deref()?;
+    if !requires_positive_arg(spec) || !cfg!(feature = "nonneg-variables") {
+        return None;
+    }
+
+    let x = variable(5);
+    let outcome = try_build(|| registry.build(atom_name, &x).map(|e| e.curvature()));
+    let (passed, message) = match outcome {
+        Ok(None) => return None,
+        Ok(Some(Curvature::Unknown)) => {
+            (true, format!("{} enforced: sign-unknown argument is unknown", requirement))
+        }
+        Ok(Some(curv)) => (
+            false,
+            format!(
+                "{} not enforced: sign-unknown argument gave {}, expected unknown",
+                requirement,
+                curvature_name(&curv)
+            ),
+        ),
+        Err(e) => (
+            true,
+            format!("{} enforced: sign-unknown argument rejected: {}", requirement, e),
+        ),
+    };
+
+    Some(ValidationCheck {
+        name: "domain_violation".to_string(),
+        passed,
+        message,
+    })
+}
+
+/// Atoms whose test expression is built over its own matrix variable rather than `x`
+const MATRIX_ARG_ATOMS: [&str; 3] = ["trace", "sigma_max", "von_neumann_entropy"];
+
+/// DCP curvature of f(g) for a convex or concave, non-affine inner g
+fn composed_curvature(outer: &str, monotonicity: &str, inner: &str) -> &'static str {
+    match (outer, monotonicity, inner) {
+        ("convex", "increasing", "convex") | ("convex", "decreasing", "concave") => "convex",
+        ("concave", "increasing", "concave") | ("concave", "decreasing", "convex") => "concave",
+        _ => "unknown",
+    }
+}
+
+/// Check the DCP composition rule implied by the spec's monotonicity on a
+/// concave argument.
+///
+/// The atom is applied to the concave inner min(a, b) over sign-unknown a, b,
+/// and the result must have the curvature the composition rule gives; the
+/// convex inner is covered by `check_composition`. Returns `None` for atoms
+/// without a simple monotonicity or a convex/concave curvature, and for atoms
+/// with a positivity requirement.
+fn check_monotonicity(
+    atom_name: &str,
+    spec: &AtomSpec,
+    expected_curv: &str,
+    registry: &AtomRegistry,
+) -> Option<ValidationCheck> {
+    let monotonicity = spec.monotonicity.as_str();
+    if !matches!(monotonicity, "increasing" | "decreasing" | "none")
+        || !matches!(expected_curv, "convex" | "concave")
+        || MATRIX_ARG_ATOMS.contains(&atom_name)
+        || requires_positive_arg(spec)
+    {
+        return None;
+    }
+
+    let (a, b) = (variable(5), variable(5));
+    let inners = [("concave", min2(&a, &b))];
+
+    for (inner_curv, inner) in &inners {
+        let expr = registry.build(atom_name, inner)?;
+        let expected = composed_curvature(expected_curv, monotonicity, inner_curv);
+        let actual = expr.curvature();
+        let passed = match expected {
+            "unknown" => matches!(actual, Curvature::Unknown),
+            _ => check_curvature(&expr, expected).passed,
+        };
+        if !passed {
+            return Some(ValidationCheck {
+                name: "monotonicity".to_string(),
+                passed: false,
+                message: format!(
+                    "{} {} of {} inner: expected {}, got {}",
+                    monotonicity,
+                    expected_curv,
+                    inner_curv,
+                    expected,
+                    curvature_name(&actual)
+                ),
+            });
+        }
+    }
+
+    Some(ValidationCheck {
+        name: "monotonicity".to_string(),
+        passed: true,
+        message: format!("{} composition rules hold", monotonicity),
+    })
+}
+
+/// The affine image A x + b of a length-5 argument, with fixed A and b
+fn affine_image(x: &Expr) -> Expr {
+    let a = DMatrix::from_fn(5, 5, |i, j| if i == j { 2.0 } else { 0.5 - (i + j) as f64 * 0.1 });
+    matmul(&constant(a), x) + vector_constant(&[1.0, -1.0, 0.5, 0.0, 2.0])
+}
+
+/// Check DCP composition over sign-unknown affine and convex arguments.
+///
+/// A convex or concave atom of an affine argument A x + b keeps its curvature.
+/// Over the convex argument max(x, y) the expected curvature follows from the
+/// spec's monotonicity, so a non-monotone atom must give unknown. Atoms with
+/// a positivity requirement are left to `check_domain_violation`.
+fn check_composition(
+    atom_name: &str,
+    spec: &AtomSpec,
+    expected_curv: &str,
+    registry: &AtomRegistry,
+) -> Option<ValidationCheck> {
+    if !matches!(expected_curv, "convex" | "concave")
+        || MATRIX_ARG_ATOMS.contains(&atom_name)
+        || requires_positive_arg(spec)
+    {
+        return None;
+    }
+
+    let x = variable(5);
+    let mut cases = vec![("affine A x + b", expected_curv, affine_image(&x))];
+    let monotonicity = spec.monotonicity.as_str();
+    if matches!(monotonicity, "increasing" | "decreasing" | "none") {
+        let expected = composed_curvature(expected_curv, monotonicity, "convex");
+        cases.push(("convex max(x, y)", expected, max2(&x, &variable(5))));
+    }
+
+    for (label, expected, inner) in &cases {
+        let expr = registry.build(atom_name, inner)?;
+        let actual = expr.curvature();
+        let passed = match *expected {
+            "unknown" => matches!(actual, Curvature::Unknown),
+            _ => check_curvature(&expr, expected).passed,
+        };
+        if !passed {
+            return Some(ValidationCheck {
+                name: "composition".to_string(),
+                passed: false,
+                message: format!(
+                    "{} of {} argument: expected {}, got {}",
+                    atom_name,
+                    label,
+                    expected,
+                    curvature_name(&actual)
+                ),
+            });
+        }
+    }
+
+    Some(ValidationCheck {
+        name: "composition".to_string(),
+        passed: true,
+        message: format!("{} composition cases hold", cases.len()),
+    })
+}
+
+/// Fixed seed for fuzz cases; a failure names its case index under this seed
+const FUZZ_SEED: u64 = 0xf022_5eed;
+
+/// Scale an expression by a constant
+fn scale(expr: &Expr, factor: f64) -> Expr {
+    expr.clone() * factor
+}
+
+/// A random affine expression A x + B over a fresh variable x of the given shape.
+///
+/// A is square with entries in [-2, 2] and B has entries of random sign, so
+/// the result has unknown sign.
+fn gen_random_affine(shape: TestShape, rng: &mut StdRng) -> Expr {
+    let x = shape.variable();
+    let (rows, cols) = matrix_dims(&x);
+    let a = DMatrix::from_fn(rows, rows, |_, _| rng.gen_range(-2.0..2.0));
+    let b = DMatrix::from_fn(rows, cols, |_, _| rng.gen_range(-2.0..2.0));
+    matmul(&constant(a), &x) + constant(b)
+}
+
+/// Classification of one fuzz case: (curvature, sign)
+fn classify(expr: &Expr) -> (&'static str, &'static str) {
+    (curvature_name(&expr.curvature()), sign_name(&expr.sign()))
+}
+
+/// Check that curvature and sign are invariant over random affine arguments.
+///
+/// For each of `cases` random arguments A x + B (over input shapes the atom
+/// supports), the atom must classify the same way after scaling the argument
+/// by a positive constant, and convex or concave atoms must keep their spec
+/// curvature. Atoms whose dcp_requires restricts the argument's sign may
+/// reject these sign-unknown arguments. Returns `None` when fuzzing is off or
+/// the atom has no test expression over `x`.
+fn check_fuzz(
+    atom_name: &str,
+    spec: &AtomSpec,
+    expected_curv: &str,
+    options: &ValidationOptions,
+) -> Option<ValidationCheck> {
+    if options.fuzz == 0 || MATRIX_ARG_ATOMS.contains(&atom_name) {
+        return None;
+    }
+
+    let shapes: Vec<TestShape> = TestShape::ALL
+        .into_iter()
+        .filter(|shape| expected_shape(atom_name, *shape, matrix_dims(&shape.variable())).is_some())
+        .collect();
+    let shapes = if shapes.is_empty() { vec![TestShape::Vector] } else { shapes };
+    let check_curv = matches!(expected_curv, "convex" | "concave") && !requires_positive_arg(spec);
+
+    let mut rng = StdRng::seed_from_u64(FUZZ_SEED);
+    for case in 0..options.fuzz {
+        let shape = shapes[rng.gen_range(0..shapes.len())];
+        let arg = gen_random_affine(shape, &mut rng);
+        let factor: f64 = rng.gen_range(0.1..10.0);
+        let context = format!(
+            "case {} (seed {:#x}, {} input, scale {})",
+            case,
+            FUZZ_SEED,
+            shape.name(),
+            factor
+        );
+        let fail = |message: String| {
+            Some(ValidationCheck {
+                name: "fuzz".to_string(),
+                passed: false,
+                message: format!("{}: {}", context, message),
+            })
+        };
+
+        let built = try_build(|| {
+            let base = options.registry.build(atom_name, &arg)?;
+            let scaled = options.registry.build(atom_name, &scale(&arg, factor))?;
+            Some((base, scaled))
+        });
+        let (base, scaled) = match built {
+            Ok(Some(pair)) => pair,
+            Ok(None) => return None,
+            // A sign-unknown argument may be rejected when dcp_requires restricts the sign
+            Err(_) if requires_positive_arg(spec) => continue,
+            Err(e) => return fail(format!("rejected random affine argument: {}", e)),
+        };
+
+        let (base_class, scaled_class) = (classify(&base), classify(&scaled));
+        if base_class != scaled_class {
+            return fail(format!(
+                "f(A x + B) is {:?} but f(c * (A x + B)) is {:?}",
+                base_class, scaled_class
+            ));
+        }
+        if check_curv && !check_curvature(&base, expected_curv).passed {
+            return fail(format!("expected {}, got {}", expected_curv, base_class.0));
+        }
+    }
+
+    Some(ValidationCheck {
+        name: "fuzz".to_string(),
+        passed: true,
+        message: format!("{} random affine arguments classified consistently", options.fuzz),
+    })
+}
+
+/// Spec name of a cvxrust curvature
+fn curvature_name(curv: &Curvature) -> &'static str {
+    match curv {
+        Curvature::Constant => "constant",
+        Curvature::Affine => "affine",
+        Curvature::Convex => "convex",
+        Curvature::Concave => "concave",
+        Curvature::Unknown => "unknown",
+    }
+}
+
+/// Spec name of a cvxrust sign
+fn sign_name(s: &cvxrust::dcp::Sign) -> &'static str {
+    match s {
+        cvxrust::dcp::Sign::Nonnegative => "nonnegative",
+        cvxrust::dcp::Sign::Nonpositive => "nonpositive",
+        cvxrust::dcp::Sign::Zero => "zero",
+        cvxrust::dcp::Sign::Unknown => "unknown",
+    }
+}
+
+/// Check if expression curvature matches expected
+fn check_curvature(expr: &Expr, expected: &str) -> ValidationCheck {
+    let curv = expr.curvature();
+    let actual = curvature_name(&curv);
+
+    let passed = match expected {
+        "constant" => curv.is_constant(),
+        "affine" => curv.is_affine(),
+        "convex" => curv.is_convex(),
+        "concave" => curv.is_concave(),
+        _ => true, // Unknown is always acceptable
+    };
+
+    ValidationCheck {
+        name: "curvature".to_string(),
+        passed,
+        message: format!("expected {}, got {}", expected, actual),
+    }
+}
+
+/// Check if expression sign matches expected
+fn check_sign(expr: &Expr, expected: &str) -> ValidationCheck {
+    let s = expr.sign();
+    let actual = sign_name(&s);
+
+    let passed = match expected {
+        "nonnegative" => matches!(s, cvxrust::dcp::Sign::Nonnegative | cvxrust::dcp::Sign::Zero),
+        "nonpositive" => matches!(s, cvxrust::dcp::Sign::Nonpositive | cvxrust::dcp::Sign::Zero),
+        "zero" => matches!(s, cvxrust::dcp::Sign::Zero),
+        _ => true, // Unknown is always acceptable
+    };
+
+    ValidationCheck {
+        name: "sign".to_string(),
+        passed,
+        message: format!("expected {}, got {}", expected, actual),
+    }
+}
+
+/// Sign of the atom applied to an argument of sign `input`, where DCP determines it
+fn propagated_sign(atom_name: &str, input: &'static str) -> Option<&'static str> {
+    match (atom_name, input) {
+        ("neg", "nonnegative") => Some("nonpositive"),
+        ("neg", "nonpositive") => Some("nonnegative"),
+        ("abs" | "pos" | "negPart" | "sumSquares" | "exp" | "norm1" | "norm2" | "normInf", _) => {
+            Some("nonnegative")
+        }
+        ("sum" | "reshape" | "transpose" | "diag" | "max" | "min", sign) => Some(sign),
+        _ => None,
+    }
+}
+
+/// Check sign inference through the atom on nonnegative and nonpositive constants.
+///
+/// Returns `None` for atoms whose output sign doesn't follow from the input sign alone.
+fn check_sign_propagation(atom_name: &str, registry: &AtomRegistry) -> Option<ValidationCheck> {
+    let inputs = [
+        ("nonnegative", [1.0, 2.0, 0.0, 0.5, 4.0]),
+        ("nonpositive", [-1.0, -2.0, 0.0, -0.5, -4.0]),
+    ];
+
+    let mut tested = Vec::new();
+    for (input_sign, values) in inputs {
+        let Some(expected) = propagated_sign(atom_name, input_sign) else {
+            continue;
+        };
+        let expr = registry.build(atom_name, &vector_constant(&values))?;
+        let check = check_sign(&expr, expected);
+        if !check.passed {
+            return Some(ValidationCheck {
+                name: "sign_propagation".to_string(),
+                passed: false,
+                message: format!("{} constant input: {}", input_sign, check.message),
+            });
+        }
+        tested.push(input_sign);
+    }
+
+    if tested.is_empty() {
+        return None;
+    }
+    Some(ValidationCheck {
+        name: "sign_propagation".to_string(),
+        passed: true,
+        message: format!("sign propagates correctly from {} constants", tested.join(" and ")),
+    })
+}
+
+/// Spec values that name a definite curvature or sign. Rule-style values such
+/// as "preserve" or "from_args", and a missing value, depend on the arguments.
+const CONCRETE_SPEC_VALUES: [&str; 7] =
+    ["constant", "affine", "convex", "concave", "nonnegative", "nonpositive", "zero"];
+
+/// Fail a lenient check whose actual value is unknown although the spec's value is concrete
+fn strict_unknown(check: ValidationCheck, expected: &str, actual: &str) -> ValidationCheck {
+    if !CONCRETE_SPEC_VALUES.contains(&expected) || actual != "unknown" {
+        return check;
+    }
+    ValidationCheck {
+        passed: false,
+        message: format!("expected {}, got unknown (strict)", expected),
+        ..check
+    }
+}
+
+/// Point the gradient check is taken at, inside every differentiable atom's domain
+const GRADIENT_POINT: [f64; 5] = [0.3, 0.7, 1.1, 1.6, 2.4];
+
+/// Central finite-difference step
+const GRADIENT_STEP: f64 = 1e-6;
+
+/// Relative tolerance between the analytic and finite-difference gradients
+const GRADIENT_TOL: f64 = 1e-5;
+
+/// Central finite-difference derivative of `f` along each coordinate of `point`
+fn central_difference<F: Fn(&[f64]) -> Option<f64>>(
+    f: F,
+    point: &[f64],
+    step: f64,
+) -> Option<Vec<f64>> {
+    let mut derivative = Vec::with_capacity(point.len());
+    for i in 0..point.len() {
+        let (mut lo, mut hi) = (point.to_vec(), point.to_vec());
+        lo[i] -= step;
+        hi[i] += step;
+        derivative.push((f(&hi)? - f(&lo)?) / (2.0 * step));
+    }
+    Some(derivative)
+}
+
+/// Check cvxrust's gradient of sum(f(x)) against central finite differences.
+///
+/// Summing reduces elementwise atoms to a scalar, so `exp` must have gradient
+/// exp(x) and `sumSquares` 2x. Returns `None` unless the spec marks the atom
+/// differentiable and it has a test expression over a length-5 vector, and
+/// without the `expr-gradient` feature.
+fn check_gradient(
+    atom_name: &str,
+    spec: &AtomSpec,
+    registry: &AtomRegistry,
+) -> Option<ValidationCheck> {
+    if !spec.differentiable || !cfg!(feature = "expr-gradient") {
+        return None;
+    }
+    let fail = |message: String| {
+        Some(ValidationCheck {
+            name: "gradient".to_string(),
+            passed: false,
+            message,
+        })
+    };
+
+    let x = test_variable(spec, GRADIENT_POINT.len());
+    let value_at = |point: &[f64]| {
+        let expr = registry.build(atom_name, &vector_constant(point))?;
+        evaluate(&sum(&expr)).map(|v| v[0])
+    };
+
+    let built = try_build(|| {
+        let expr = sum(&registry.build(atom_name, &x)?);
+        Some(gradient(&expr, &x, &GRADIENT_POINT))
+    });
+    let analytic = match built {
+        Ok(Some(Some(g))) => g,
+        Ok(Some(None)) => return fail(format!("no gradient at x = {:?}", GRADIENT_POINT)),
+        Ok(None) => return None,
+        Err(e) => return fail(format!("gradient panicked: {}", e)),
+    };
+
+    let Some(numeric) = central_difference(value_at, &GRADIENT_POINT, GRADIENT_STEP) else {
+        return fail(format!("could not evaluate {} near x = {:?}", atom_name, GRADIENT_POINT));
+    };
+
+    if analytic.len() != numeric.len() {
+        return fail(format!(
+            "gradient has {} entries, expected {}",
+            analytic.len(),
+            numeric.len()
+        ));
+    }
+    let max_err = analytic.iter().zip(&numeric).map(|(a, n)| (a - n).abs()).fold(0.0, f64::max);
+    let scale = numeric.iter().map(|n| n.abs()).fold(1.0, f64::max);
+    if max_err > GRADIENT_TOL * scale {
+        return fail(format!(
+            "at x = {:?}: analytic {:?}, finite difference {:?}, max error {:e}",
+            GRADIENT_POINT, analytic, numeric, max_err
+        ));
+    }
+
+    Some(ValidationCheck {
+        name: "gradient".to_string(),
+        passed: true,
+        message: format!("matches finite differences (max error {:e})", max_err),
+    })
+}
+
+/// Check that negating the test expression flips its classification.
+///
+/// Convex and concave swap, affine and constant stay put, and nonnegative and
+/// nonpositive swap; unknown must stay unknown.
+fn check_negation(expr: &Expr) -> ValidationCheck {
+    let (curv, sign) = classify(expr);
+    let expected_curv = match curv {
+        "convex" => "concave",
+        "concave" => "convex",
+        other => other,
+    };
+    let expected_sign = match sign {
+        "nonnegative" => "nonpositive",
+        "nonpositive" => "nonnegative",
+        other => other,
+    };
+
+    let (passed, message) = match try_build(|| classify(&negate(expr))) {
+        Ok((neg_curv, neg_sign)) if neg_curv == expected_curv && neg_sign == expected_sign => {
+            (true, format!("-f is {} and {}", neg_curv, neg_sign))
+        }
+        Ok((neg_curv, neg_sign)) => (
+            false,
+            format!(
+                "f is {} and {}, so -f should be {} and {}, got {} and {}",
+                curv, sign, expected_curv, expected_sign, neg_curv, neg_sign
+            ),
+        ),
+        Err(e) => (false, format!("negation panicked: {}", e)),
+    };
+
+    ValidationCheck {
+        name: "negation".to_string(),
+        passed,
+        message,
+    }
+}
+
+/// Fixed seed for the identity check's random constants
+const IDENTITY_SEED: u64 = 0x1de7_0000;
+
+/// An algebraic identity `nested(x) == single(x)` involving one atom
+struct AtomIdentity {
+    atom: &'static str,
+    label: &'static str,
+    /// Shape of the sampled argument
+    arg_shape: (usize, usize),
+    nested: fn(&Expr) -> Expr,
+    single: fn(&Expr) -> Expr,
+}
+
+/// Identities checked by `check_identities`; add a row here to cover a new one
+fn atom_identities() -> Vec<AtomIdentity> {
+    vec![
+        AtomIdentity {
+            atom: "abs",
+            label: "abs(abs(x)) == abs(x)",
+            arg_shape: (5, 1),
+            nested: |x| abs(&abs(x)),
+            single: |x| abs(x),
+        },
+        AtomIdentity {
+            atom: "pos",
+            label: "pos(pos(x)) == pos(x)",
+            arg_shape: (5, 1),
+            nested: |x| pos(&pos(x)),
+            single: |x| pos(x),
+        },
+        // negPart(negPart(x)) is identically 0, since negPart(x) >= 0; negating
+        // the inner result gives an identity that keeps the convex curvature
+        AtomIdentity {
+            atom: "negPart",
+            label: "negPart(-negPart(x)) == negPart(x)",
+            arg_shape: (5, 1),
+            nested: |x| neg_part(&negate(&neg_part(x))),
+            single: |x| neg_part(x),
+        },
+        AtomIdentity {
+            atom: "transpose",
+            label: "transpose(transpose(M)) == M",
+            arg_shape: (2, 3),
+            nested: |x| transpose(&transpose(x)),
+            single: |x| x.clone(),
+        },
+    ]
+}
+
+/// Check the atom's identities from `atom_identities` on a random constant,
+/// and that the nested form has the same curvature as the single form.
+/// Returns `None` if the atom has no identities.
+fn check_identities(atom_name: &str, tol: f64) -> Option<ValidationCheck> {
+    let identities: Vec<AtomIdentity> =
+        atom_identities().into_iter().filter(|id| id.atom == atom_name).collect();
+    if identities.is_empty() {
+        return None;
+    }
+    let fail = |message: String| {
+        Some(ValidationCheck {
+            name: "identity".to_string(),
+            passed: false,
+            message,
+        })
+    };
+
+    let mut rng = StdRng::seed_from_u64(IDENTITY_SEED);
+    for id in &identities {
+        let (rows, cols) = id.arg_shape;
+        let data = DMatrix::from_fn(rows, cols, |_, _| rng.gen_range(-3.0..3.0));
+        let built = try_build(|| {
+            let c = constant(data.clone());
+            let x = variable([rows, cols]);
+            (
+                evaluate(&(id.nested)(&c)),
+                evaluate(&(id.single)(&c)),
+                curvature_name(&(id.nested)(&x).curvature()),
+                curvature_name(&(id.single)(&x).curvature()),
+            )
+        });
+        let (nested_value, single_value, nested_curv, single_curv) = match built {
+            Ok(outcome) => outcome,
+            Err(e) => return fail(format!("{}: panicked: {}", id.label, e)),
+        };
+
+        match (nested_value, single_value) {
+            (Some(l), Some(r)) if l.shape() == r.shape() && (&l - &r).amax() <= tol => {}
+            (Some(l), Some(r)) => {
+                return fail(format!(
+                    "{} at x = {}: got {} vs {}",
+                    id.label,
+                    format_matrix(&data),
+                    format_matrix(&l),
+                    format_matrix(&r)
+                ))
+            }
+            _ => return fail(format!("{}: could not evaluate", id.label)),
+        }
+        if nested_curv != single_curv {
+            return fail(format!(
+                "{}: nested form is {} but single form is {}",
+                id.label, nested_curv, single_curv
+            ));
+        }
+    }
+
+    let labels: Vec<&str> = identities.iter().map(|id| id.label).collect();
+    Some(ValidationCheck {
+        name: "identity".to_string(),
+        passed: true,
+        message: format!("holds: {}", labels.join("; ")),
+    })
+}
+
+/// Validate a single atom against its specification
+pub fn validate_atom(
+    atom_name: &str,
+    spec: &AtomSpec,
+    category: &str,
+    options: &ValidationOptions,
+) -> ValidationResult {
+    if let Some(e) = &spec.parse_error {
+        return ValidationResult {
+            atom_name: atom_name.to_string(),
+            passed: false,
+            checks: vec![ValidationCheck {
+                name: "spec_parse".to_string(),
+                passed: false,
+                message: format!("invalid atoms.yaml entry: {}", e),
+            }],
+            duration: Duration::ZERO,
+        };
+    }
+
+    let mut checks = Vec::new();
+
+    // Create test variable. Atoms whose dcp_requires restricts the argument's
+    // sign are classified over an argument that meets the requirement; the
+    // sign-unknown case is left to check_domain_violation.
+    let x = test_variable(spec, 5);
+
+    // Create test expression
+    let built = match try_build(|| options.registry.build(atom_name, &x)) {
+        Ok(Some(e)) => Ok(e),
+        Ok(None) => Err(format!("atom '{}' not implemented in cvxrust", atom_name)),
+        Err(e) => Err(format!("building the test expression panicked: {}", e)),
+    };
+    let expr = match built {
+        Ok(e) => {
+            checks.push(ValidationCheck {
+                name: "exists".to_string(),
+                passed: true,
+                message: "atom exists in cvxrust".to_string(),
+            });
+            e
+        }
+        Err(message) => {
+            checks.push(ValidationCheck {
+                name: "exists".to_string(),
+                passed: false,
+                message,
+            });
+            return ValidationResult {
+                atom_name: atom_name.to_string(),
+                passed: false,
+                checks,
+                duration: Duration::ZERO,
+            };
+        }
+    };
+
+    // Check curvature
+    let expected_curv = if category == "affine" {
+        "affine"
+    } else {
+        // A conditional rule the test parameters can't decide falls back to the category
+        spec.curvature.resolve(&test_expr_params(atom_name)).unwrap_or(category)
+    };
+    let mut curv = check_curvature(&expr, expected_curv);
+    if options.strict {
+        curv = strict_unknown(curv, expected_curv, curvature_name(&expr.curvature()));
+    }
+    checks.push(curv);
+
+    // Check sign
+    let mut sign = check_sign(&expr, &spec.sign);
+    if options.strict {
+        sign = strict_unknown(sign, &spec.sign, sign_name(&expr.sign()));
+    }
+    checks.push(sign);
+
+    // Check arity and result shape
+    if let Some(check) = check_arity(atom_name, spec, &x, &expr) {
+        checks.push(check);
+    }
+
+    // Check sign inference from signed constant inputs
+    if let Some(check) = check_sign_propagation(atom_name, &options.registry) {
+        checks.push(check);
+    }
+
+    // Check output shapes across input shapes
+    if let Some(check) = check_shape(atom_name, spec, &options.registry) {
+        checks.push(check);
+    }
+
+    // Check a sign requirement on the argument is enforced; the atom's own
+    // curvature was checked above on an argument that meets it
+    if let Some(check) = check_domain_violation(atom_name, spec, &options.registry) {
+        checks.push(check);
+    }
+
+    // Check monotonicity-driven composition with a concave argument
+    if let Some(check) = check_monotonicity(atom_name, spec, expected_curv, &options.registry) {
+        checks.push(check);
+    }
+
+    // Check composition with affine and convex arguments
+    if let Some(check) = check_composition(atom_name, spec, expected_curv, &options.registry) {
+        checks.push(check);
+    }
+
+    // Check unary minus flips curvature and sign
+    checks.push(check_negation(&expr));
+
+    // Check classification is stable over random affine arguments
+    if let Some(check) = check_fuzz(atom_name, spec, expected_curv, options) {
+        checks.push(check);
+    }
+
+    // Atom-specific checks
+    if atom_name == "abs" {
+        checks.push(check_matrix_elementwise(abs, expected_curv, &spec.sign));
+    }
+    if atom_name == "quadForm" {
+        checks.push(check_quad_form_psd());
+    }
+    if atom_name == "vstack" || atom_name == "hstack" {
+        checks.push(check_stack_shapes(atom_name));
+    }
+    if atom_name == "reshape" {
+        checks.push(check_reshape_elements());
+    }
+    #[cfg(feature = "berhu")]
+    if atom_name == "berhu" {
+        checks.push(check_rejects_parameter("threshold M = 0", || berhu(&x, 0.0)));
+    }
+    #[cfg(feature = "log-loss")]
+    if atom_name == "log_loss" {
+        checks.push(check_rejects_parameter("label 0.5", || {
+            log_loss(&x, &[0.0, 0.5, 1.0, 0.0, 1.0])
+        }));
+        checks.push(check_rejects_parameter("label count", || log_loss(&x, &[0.0, 1.0])));
+    }
+
+    // Checks below evaluate the atom, which needs cvxrust's Expr::value
+    if cfg!(feature = "expr-value") {
+        checks.extend(numeric_checks(atom_name, spec, category, options));
+    }
+
+    let all_passed = checks.iter().all(|c| c.passed);
+    ValidationResult {
+        atom_name: atom_name.to_string(),
+        passed: all_passed,
+        checks,
+        duration: Duration::ZERO,
+    }
+}
+
+/// Checks that evaluate the atom. Those that compare against the global
+/// tolerance use the atom's `numeric_tol` instead when the spec sets one.
+fn numeric_checks(
+    atom_name: &str,
+    spec: &AtomSpec,
+    category: &str,
+    options: &ValidationOptions,
+) -> Vec<ValidationCheck> {
+    let tol = spec.tol(options.tol);
+    let mut checks = Vec::new();
+
+    // Check affine atoms compute the right linear map
+    if category == "affine" {
+        if let Some(check) = check_linearity(atom_name, tol) {
+            checks.push(check);
+        }
+    }
+
+    // Check algebraic identities such as abs(abs(x)) == abs(x)
+    if let Some(check) = check_identities(atom_name, tol) {
+        checks.push(check);
+    }
+
+    // Symbolic-only atoms skip the gradient check and test vectors
+    if !spec.skip_numeric {
+        // Check the analytic gradient of smooth atoms
+        if let Some(check) = check_gradient(atom_name, spec, &options.registry) {
+            checks.push(check);
+        }
+
+        // Check reference values from the spec
+        for vector in &spec.test_vectors {
+            checks.push(check_numeric(atom_name, spec, vector, options));
+        }
+    }
+
+    // Atom-specific checks
+    if atom_name == "norm2" {
+        checks.push(check_frobenius_norm(tol));
+    }
+    if atom_name == "quadOverLin" {
+        checks.push(check_perspective(tol));
+    }
+    if atom_name == "reshape" {
+        checks.push(check_shape_roundtrip(tol));
+        checks.push(check_reshape_layout(tol));
+    }
+    #[cfg(feature = "berhu")]
+    if atom_name == "berhu" {
+        let a = DMatrix::from_column_slice(5, 1, &[-3.0, -0.5, 0.0, 0.5, 3.0]);
+        let b = DMatrix::from_column_slice(5, 1, &[2.0, -2.0, 1.0, -4.0, 0.25]);
+        let build = |arg: &Expr| Some(berhu(arg, 1.0));
+        checks.push(check_numeric_curvature(build, &a, &b, "convex", tol));
+    }
+    #[cfg(feature = "log-loss")]
+    if atom_name == "log_loss" {
+        let scores = [-2.0, -0.5, 0.0, 1.5, 3.0];
+        let expected: f64 = scores
+            .iter()
+            .zip(LOG_LOSS_LABELS)
+            .map(|(s, y)| (1.0 + s.exp()).ln() - y * s)
+            .sum();
+        let expr = log_loss(&vector_constant(&scores), &LOG_LOSS_LABELS);
+        checks.push(check_numeric_value(&expr, &[expected], tol));
+
+        let a = DMatrix::from_column_slice(5, 1, &scores);
+        let b = DMatrix::from_column_slice(5, 1, &[1.0, 2.0, -3.0, 0.5, -1.0]);
+        checks.push(check_numeric_curvature(
+            |arg| Some(log_loss(arg, &LOG_LOSS_LABELS)),
+            &a,
+            &b,
+            "convex",
+            tol,
+        ));
+    }
+    #[cfg(feature = "von-neumann-entropy")]
+    if atom_name == "von_neumann_entropy" {
+        // Symmetric, diagonally dominant (hence positive definite) test matrices
+        let a = DMatrix::from_row_slice(3, 3, &[2.0, 0.5, 0.0, 0.5, 1.0, 0.2, 0.0, 0.2, 1.5]);
+        let b = DMatrix::from_row_slice(3, 3, &[1.0, -0.3, 0.1, -0.3, 2.0, 0.0, 0.1, 0.0, 0.8]);
+        let build = |arg: &Expr| Some(von_neumann_entropy(arg));
+        checks.push(check_numeric_curvature(build, &a, &b, "concave", tol));
+
+        // Symmetric with eigenvalues {-1, 1, 3}
+        let indefinite =
+            DMatrix::from_row_slice(3, 3, &[1.0, 2.0, 0.0, 2.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+        checks.push(check_outside_domain(von_neumann_entropy, &indefinite, "PSD"));
+    }
+    #[cfg(feature = "log1p")]
+    if atom_name == "log1p" {
+        // A naive log(1 + x) loses about 8 significant digits at x = 1e-10
+        let small = [1e-10, -1e-12, 1e-15, 3e-8, 0.0];
+        let expected: Vec<f64> = small.iter().map(|v| v.ln_1p()).collect();
+        checks.push(check_numeric_value(&log1p(&vector_constant(&small)), &expected, 1e-20));
+
+        let below = DMatrix::from_column_slice(5, 1, &[-2.0, -1.5, -3.0, -10.0, -1.25]);
+        checks.push(check_outside_domain(log1p, &below, "x > -1"));
+    }
+
+    checks
+}
+
+/// Validate one instantiation of a parameterized atom as its own result
+pub fn validate_instantiation(
+    atom_name: &str,
+    spec: &AtomSpec,
+    instantiation: &Instantiation,
+    options: &ValidationOptions,
+) -> ValidationResult {
+    let label = instantiation.label(atom_name);
+    let expected = instantiation
+        .curvature
+        .as_deref()
+        .or_else(|| spec.curvature.resolve(&instantiation.params))
+        .unwrap_or("unknown");
+    let x = match instantiation.domain.as_deref() {
+        Some("positive" | "nonnegative") => nonneg_variable(5),
+        _ => variable(5),
+    };
+
+    let params = &instantiation.params;
+    let build = |arg: &Expr| options.registry.build_param(atom_name, arg, params);
+    let checks = match try_build(|| build(&x)) {
+        Ok(Some(expr)) => {
+            let mut curv = check_curvature(&expr, expected);
+            if options.strict {
+                curv = strict_unknown(curv, expected, curvature_name(&expr.curvature()));
+            }
+            let mut checks = vec![curv];
+            // Sample points are positive, so they lie in every instantiation's domain
+            if cfg!(feature = "expr-value") && matches!(expected, "convex" | "concave") {
+                let a = DMatrix::from_column_slice(5, 1, &[0.5, 1.0, 1.5, 2.0, 2.5]);
+                let b = DMatrix::from_column_slice(5, 1, &[3.0, 0.2, 1.2, 0.8, 4.0]);
+                let tol = spec.tol(options.tol);
+                checks.push(check_numeric_curvature(build, &a, &b, expected, tol));
+            }
+            checks
+        }
+        Ok(None) => vec![ValidationCheck {
+            name: "exists".to_string(),
+            passed: false,
+            message: format!("no parameterized test expression for {}", label),
+        }],
+        Err(e) => vec![ValidationCheck {
+            name: "exists".to_string(),
+            passed: false,
+            message: format!("{} rejected: {}", label, e),
+        }],
+    };
+
+    ValidationResult {
+        atom_name: label,
+        passed: checks.iter().all(|c| c.passed),
+        checks,
+        duration: Duration::ZERO,
+    }
+}
+
+/// Run `validate`, recording how long it took in the result. A panic that
+/// escapes the checks becomes a failed "panic" check for `name`, so one atom
+/// can't abort the whole run.
+fn timed<F: FnOnce() -> ValidationResult>(name: &str, validate: F) -> ValidationResult {
+    let start = Instant::now();
+    let mut result = try_build(validate).unwrap_or_else(|e| ValidationResult {
+        atom_name: name.to_string(),
+        passed: false,
+        checks: vec![ValidationCheck {
+            name: "panic".to_string(),
+            passed: false,
+            message: format!("validation panicked: {}", e),
+        }],
+        duration: Duration::ZERO,
+    });
+    result.duration = start.elapsed();
+    result
+}
+
+/// Validate the atoms selected by `filter`, returning results sorted by atom name.
+///
+/// Fails only if the worker thread pool can't be created.
+pub fn validate_all(
+    specs: &HashMap<String, (AtomSpec, &str)>,
+    filter: &AtomFilter,
+    options: &ValidationOptions,
+) -> Result<Vec<ValidationResult>, String> {
+    // List of atoms we want to validate (ones implemented in cvxrust)
+    let default_atoms = vec![
+        "neg",
+        "sum",
+        "reshape",
+        "transpose",
+        "trace",
+        "diag",
+        "vstack",
+        "hstack",
+        "norm1",
+        "norm2",
+        "normInf",
+        "abs",
+        "pos",
+        "negPart",
+        "maximum",
+        "sumSquares",
+        "quadForm",
+        "quadOverLin",
+        "exp",
+        "sigma_max",
+        "berhu",
+        "log_loss",
+        "indicator",
+        "log",
+        "log1p",
+        "entropy",
+        "sqrt",
+        "minimum",
+        "power",
+        "von_neumann_entropy",
+        "max",
+        "min",
+        "log_sum_exp",
+        "geo_mean",
+        "huber",
+        "inv_pos",
+    ];
+
+    // Explicitly requested atoms are validated even if they aren't in the default list
+    let mut atoms_to_validate: Vec<&str> = if filter.atoms.is_empty() {
+        default_atoms
+    } else {
+        filter.atoms.iter().map(String::as_str).collect()
+    };
+
+    // Unparseable entries are always reported, even for atoms not validated by default
+    if filter.atoms.is_empty() {
+        let mut broken: Vec<&str> = specs
+            .iter()
+            .filter(|(name, (spec, _))| {
+                spec.parse_error.is_some() && !atoms_to_validate.contains(&name.as_str())
+            })
+            .map(|(name, _)| name.as_str())
+            .collect();
+        broken.sort();
+        atoms_to_validate.extend(broken);
+    }
+
+    let mut selected: Vec<(&str, &AtomSpec, &str)> = Vec::new();
+
+    for atom_name in atoms_to_validate {
+        match specs.get(atom_name) {
+            Some((spec, category)) => {
+                if filter.selects(atom_name, Some(*category)) {
+                    selected.push((atom_name, spec, category));
+                }
+            }
+            None if !filter.atoms.is_empty() => {
+                eprintln!("warning: requested atom '{}' is not in the specs", atom_name);
+            }
+            None => {}
+        }
+    }
+
+    // Each atom builds its own expressions, so atoms validate independently.
+    // Parameterized atoms add one result per instantiation after their own.
+    let validate = |&(atom_name, spec, category): &(&str, &AtomSpec, &str)| {
+        let mut group =
+            vec![timed(atom_name, || validate_atom(atom_name, spec, category, options))];
+        for instantiation in &spec.instantiations {
+            let label = instantiation.label(atom_name);
+            group.push(timed(&label, || {
+                validate_instantiation(atom_name, spec, instantiation, options)
+            }));
+        }
+        group
+    };
+    let groups: Vec<Vec<ValidationResult>> = match options.jobs {
+        Some(1) => selected.iter().map(validate).collect(),
+        Some(jobs) => ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()
+            .map_err(|e| format!("Failed to build validation thread pool: {}", e))?
+            .install(|| selected.par_iter().map(validate).collect()),
+        None => selected.par_iter().map(validate).collect(),
+    };
+
+    let mut results: Vec<ValidationResult> = groups.into_iter().flatten().collect();
+    results.sort_by(|a, b| a.atom_name.cmp(&b.atom_name));
+    Ok(results)
+}
+
+/// Spec atoms that `registry` has no test expression for, sorted by name.
+///
+/// Each atom is built over the argument `validate_atom` uses; an atom whose
+/// build panics has a test expression and is reported by its own checks.
+pub fn untested_atoms(
+    specs: &HashMap<String, (AtomSpec, &str)>,
+    registry: &AtomRegistry,
+) -> Vec<String> {
+    let mut untested: Vec<String> = specs
+        .iter()
+        .filter(|(name, (spec, _))| {
+            let x = test_variable(spec, 5);
+            matches!(try_build(|| registry.build(name, &x)), Ok(None))
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    untested.sort();
+    untested
+}
+
+/// Write the human-readable report; its layout is pinned by tests/report_snapshot.rs
+pub fn print_results<W: Write>(results: &[ValidationResult], out: &mut W) -> io::Result<()> {
+    let passed = results.iter().filter(|r| r.passed).count();
+    let total = results.len();
+
+    writeln!(out, "\n{}", "=".repeat(60))?;
+    writeln!(out, "CVXRUST Validation Results: {}/{} atoms passed", passed, total)?;
+    writeln!(out, "{}\n", "=".repeat(60))?;
+
+    // Print failures first
+    let failures: Vec<_> = results.iter().filter(|r| !r.passed).collect();
+    if !failures.is_empty() {
+        writeln!(out, "FAILURES:")?;
+        writeln!(out, "{}", "-".repeat(40))?;
+        for result in failures {
+            writeln!(out, "\n  {}:", result.atom_name)?;
+            for check in result.failed_checks() {
+                writeln!(out, "    FAIL {}: {}", check.name, check.message)?;
+            }
+        }
+    }
+
+    // Print successes
+    let successes: Vec<_> = results.iter().filter(|r| r.passed).collect();
+    if !successes.is_empty() {
+        writeln!(out, "\nPASSED ({}):", successes.len())?;
+        writeln!(out, "{}", "-".repeat(40))?;
+        for result in successes {
+            writeln!(out, "  {}", result.atom_name)?;
+        }
+    }
+
+    writeln!(out)
+}
+
+/// Print how long each atom took to validate, slowest first
+pub fn print_timing<W: Write>(results: &[ValidationResult], out: &mut W) -> io::Result<()> {
+    let mut timed: Vec<_> = results.iter().collect();
+    timed.sort_by(|a, b| b.duration.cmp(&a.duration));
+    let total: Duration = results.iter().map(|r| r.duration).sum();
+
+    writeln!(out, "TIMING (total {:.1} ms):", total.as_secs_f64() * 1e3)?;
+    writeln!(out, "{}", "-".repeat(40))?;
+    for result in timed {
+        writeln!(
+            out,
+            "  {:<30} {:>10.3} ms",
+            result.atom_name,
+            result.duration.as_secs_f64() * 1e3
+        )?;
+    }
+    writeln!(out)
+}
+
+/// Print the atoms whose validation took longer than `threshold`
+pub fn print_slow<W: Write>(
+    results: &[ValidationResult],
+    threshold: Duration,
+    out: &mut W,
+) -> io::Result<()> {
+    let mut slow: Vec<_> = results.iter().filter(|r| r.duration > threshold).collect();
+    slow.sort_by(|a, b| b.duration.cmp(&a.duration));
+
+    let threshold_ms = threshold.as_millis();
+    if slow.is_empty() {
+        return writeln!(out, "No atom took longer than {} ms\n", threshold_ms);
+    }
+    writeln!(out, "SLOW (over {} ms, {}):", threshold_ms, slow.len())?;
+    writeln!(out, "{}", "-".repeat(40))?;
+    for result in slow {
+        writeln!(
+            out,
+            "  {:<30} {:>10.3} ms",
+            result.atom_name,
+            result.duration.as_secs_f64() * 1e3
+        )?;
+    }
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(values: &[(&str, f64)]) -> BTreeMap<String, f64> {
+        values.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn eval_condition_cases() {
+        let cases: [(&str, &[(&str, f64)], Option<bool>); 10] = [
+            ("p == 0", &[("p", 0.0)], Some(true)),
+            ("p == 0", &[("p", 1.0)], Some(false)),
+            ("p >= 1 or p < 0", &[("p", 2.0)], Some(true)),
+            ("p >= 1 or p < 0", &[("p", -1.0)], Some(true)),
+            ("p >= 1 or p < 0", &[("p", 0.5)], Some(false)),
+            ("0 < p < 1", &[("p", 0.5)], Some(true)),
+            ("0 < p < 1", &[("p", 1.0)], Some(false)),
+            ("M > 0 and M != 2", &[("M", 2.0)], Some(false)),
+            ("p >= 0 and arg is nonneg", &[("p", 1.0)], None),
+            ("q > 0", &[("p", 1.0)], None),
+        ];
+        for (condition, values, expected) in cases {
+            assert_eq!(eval_condition(condition, &params(values)), expected, "{}", condition);
+        }
+    }
+
+    #[test]
+    fn conditional_curvature_resolves_power_cases() {
+        let spec: CurvatureSpec = serde_yaml::from_str(
+            r#"
+            rule: conditional
+            cases:
+              - if: "p == 0"
+                then: constant
+              - if: "p == 1"
+                then: affine
+              - if: "p >= 1 or p < 0"
+                then: convex
+              - if: "0 < p < 1"
+                then: concave
+            "#,
+        )
+        .expect("curvature rule parses");
+        assert!(matches!(spec, CurvatureSpec::Complex(_)));
+        assert_eq!(spec.as_str(), "unknown");
+
+        let cases = [
+            (0.0, Some("constant")),
+            (1.0, Some("affine")),
+            (2.0, Some("convex")),
+            (-1.0, Some("convex")),
+            (0.5, Some("concave")),
+        ];
+        for (p, expected) in cases {
+            assert_eq!(spec.resolve(&params(&[("p", p)])), expected, "p = {}", p);
+        }
+    }
+
+    #[test]
+    fn conditional_curvature_falls_back_to_else_or_none() {
+        let spec: CurvatureSpec = serde_yaml::from_str(
+            r#"
+            rule: conditional
+            cases:
+              - if: "P is PSD"
+                then: convex
+              - else: unknown
+            "#,
+        )
+        .expect("curvature rule parses");
+        assert_eq!(spec.resolve(&params(&[])), None);
+
+        let spec: CurvatureSpec = serde_yaml::from_str(
+            r#"
+            rule: conditional
+            cases:
+              - if: "M < 0"
+                then: concave
+              - else: convex
+            "#,
+        )
+        .expect("curvature rule parses");
+        assert_eq!(spec.resolve(&params(&[("M", 1.0)])), Some("convex"));
+
+        let spec: CurvatureSpec =
+            serde_yaml::from_str("rule: composition").expect("curvature rule parses");
+        assert_eq!(spec.resolve(&params(&[("p", 1.0)])), None);
+        assert_eq!(CurvatureSpec::Simple("convex".into()).resolve(&params(&[])), Some("convex"));
+    }
+
+    #[test]
+    fn requires_positive_arg_reads_only_the_test_argument() {
+        let cases = [
+            (Some("concave_positive_arg"), true),
+            (Some("concave_nonneg_arg"), true),
+            (Some("affine_positive_arg"), true),
+            (Some("affine_x, concave_positive_y"), false),
+            (Some("affine_arg"), false),
+            (None, false),
+        ];
+        for (requires, expected) in cases {
+            let spec = AtomSpec {
+                dcp_requires: requires.map(str::to_string),
+                ..AtomSpec::default()
+            };
+            assert_eq!(requires_positive_arg(&spec), expected, "{:?}", requires);
+        }
+    }
+
+    #[test]
+    fn expected_shape_cases() {
+        let cases = [
+            ("sum", TestShape::Matrix, (3, 3), Some((1, 1))),
+            ("norm1", TestShape::Matrix, (3, 3), None),
+            ("exp", TestShape::Vector, (5, 1), Some((5, 1))),
+            ("transpose", TestShape::Vector, (5, 1), Some((1, 5))),
+            ("reshape", TestShape::Matrix, (3, 3), Some((1, 9))),
+            ("vstack", TestShape::Vector, (5, 1), Some((10, 1))),
+            ("hstack", TestShape::Vector, (5, 1), Some((5, 2))),
+            ("diag", TestShape::Vector, (5, 1), Some((5, 5))),
+            ("diag", TestShape::Matrix, (3, 3), Some((3, 1))),
+            ("diag", TestShape::Scalar, (1, 1), None),
+            ("trace", TestShape::Vector, (5, 1), None),
+            ("quadForm", TestShape::Vector, (5, 1), Some((1, 1))),
+            ("quadForm", TestShape::Matrix, (3, 3), None),
+        ];
+        for (atom, shape, dims, expected) in cases {
+            assert_eq!(expected_shape(atom, shape, dims), expected, "{} {:?}", atom, shape);
+        }
+    }
+
+    #[test]
+    fn central_difference_matches_known_gradients() {
+        let point = [0.5, -1.0, 2.0];
+        let squares = central_difference(
+            |x| Some(x.iter().map(|v| v * v).sum()),
+            &point,
+            GRADIENT_STEP,
+        )
+        .expect("sum of squares evaluates");
+        for (d, x) in squares.iter().zip(point) {
+            assert!((d - 2.0 * x).abs() < 1e-8, "d/dx x^2 at {}: {}", x, d);
+        }
+
+        let exps = central_difference(
+            |x| Some(x.iter().map(|v| v.exp()).sum()),
+            &point,
+            GRADIENT_STEP,
+        )
+        .expect("sum of exp evaluates");
+        for (d, x) in exps.iter().zip(point) {
+            assert!((d - x.exp()).abs() <= GRADIENT_TOL * x.exp(), "d/dx e^x at {}: {}", x, d);
+        }
+
+        assert_eq!(central_difference(|_| None, &point, GRADIENT_STEP), None);
+    }
+
+    #[test]
+    fn column_major_reshape_reads_down_columns() {
+        let data = DMatrix::from_row_slice(2, 3, &[1.0, 3.0, 5.0, 2.0, 4.0, 6.0]);
+        let reshaped = column_major_reshape(&data, 3, 2);
+        assert_eq!(reshaped, DMatrix::from_row_slice(3, 2, &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0]));
+        assert_eq!(column_major_reshape(&reshaped, 2, 3), data);
+        assert_eq!(
+            column_major_reshape(&data, 1, 6),
+            DMatrix::from_row_slice(1, 6, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
+        );
+    }
+
+    #[test]
+    fn merge_yaml_overlay_takes_precedence() {
+        let mut base: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+            convex_atoms:
+              abs:
+                curvature: convex
+                sign: nonnegative
+                monotonicity: none
+            "#,
+        )
+        .expect("base parses");
+        let overlay: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+            convex_atoms:
+              abs:
+                sign: unknown
+                test_vectors:
+                  - input: [-1]
+                    expected: 1
+              vendor_atom:
+                curvature: convex
+            concave_atoms:
+              vendor_log:
+                curvature: concave
+            "#,
+        )
+        .expect("overlay parses");
+        merge_yaml(&mut base, overlay);
+
+        let abs = &base["convex_atoms"]["abs"];
+        let cases = [
+            ("curvature", Some("convex")), // kept from the base
+            ("monotonicity", Some("none")), // kept from the base
+            ("sign", Some("unknown")), // overridden by the overlay
+        ];
+        for (field, expected) in cases {
+            assert_eq!(abs[field].as_str(), expected, "{}", field);
+        }
+        assert_eq!(abs["test_vectors"].as_sequence().map(Vec::len), Some(1));
+        assert_eq!(base["convex_atoms"]["vendor_atom"]["curvature"].as_str(), Some("convex"));
+        assert_eq!(base["concave_atoms"]["vendor_log"]["curvature"].as_str(), Some("concave"));
+    }
+
+    #[test]
+    fn merge_yaml_replaces_non_mappings() {
+        let mut base: serde_yaml::Value =
+            serde_yaml::from_str("dcp_requires: [a, b]").expect("base parses");
+        let overlay: serde_yaml::Value =
+            serde_yaml::from_str("dcp_requires: c").expect("overlay parses");
+        merge_yaml(&mut base, overlay);
+        assert_eq!(base["dcp_requires"].as_str(), Some("c"));
+    }
+}
@@ -1,356 +1,473 @@
-//! Validate cvxrust implementation against CVX-Core specifications.
-//!
-//! This validator tests that cvxrust's atoms behave according to the
-//! canonical specifications in specs/atoms.yaml.
+//! Command-line entry point for the cvx-validator library.
 
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-
-use cvxrust::prelude::*;
-use serde::Deserialize;
-
-/// Specification for a single atom from atoms.yaml
-#[derive(Debug, Clone, Deserialize)]
-struct AtomSpec {
-    #[serde(default)]
-    description: String,
-    #[serde(default)]
-    curvature: CurvatureSpec,
-    #[serde(default)]
-    sign: String,
-    #[serde(default)]
-    arity: String,
-    #[serde(default)]
-    dcp_requires: Option<String>,
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{Parser, ValueEnum};
+use cvx_validator::{
+    baseline, graph, load_specs, parity, print_results, print_slow, print_timing, untested_atoms,
+    validate_all, AtomFilter, AtomSpec, CurvatureSpec, ValidationOptions, ValidationResult,
+};
+
+/// Exit status when any atom fails validation
+const EXIT_FAILURES: i32 = 1;
+
+/// Exit status for internal errors, such as a missing or unparseable spec file
+const EXIT_INTERNAL_ERROR: i32 = 2;
+
+/// How results are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable report
+    Human,
+    /// JSON array of per-atom results
+    Json,
+    /// JUnit XML, one testcase per atom
+    Junit,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
-#[serde(untagged)]
-enum CurvatureSpec {
-    Simple(String),
-    Complex(HashMap<String, serde_yaml::Value>),
-    #[default]
-    Unknown,
+/// Validate cvxrust against the CVX-Core atom specifications
+#[derive(Debug, Parser)]
+#[command(name = "cvx-validator")]
+struct Cli {
+    /// Directory containing atoms.yaml (default: the cvx-core checkout this crate lives in)
+    #[arg(long, value_name = "PATH", env = "CVX_SPECS_DIR")]
+    specs_dir: Option<PathBuf>,
+
+    /// Overlay spec file merged over atoms.yaml, in order (repeatable)
+    #[arg(long = "extra-specs", value_name = "PATH")]
+    extra_specs: Vec<PathBuf>,
+
+    /// Only validate this atom (repeatable)
+    #[arg(long = "atom", value_name = "NAME")]
+    atoms: Vec<String>,
+
+    /// Only validate atoms in this spec category (repeatable)
+    #[arg(
+        long = "category",
+        value_name = "CATEGORY",
+        value_parser = ["affine", "convex", "concave"]
+    )]
+    categories: Vec<String>,
+
+    /// Skip this atom (repeatable)
+    #[arg(long, value_name = "NAME")]
+    exclude: Vec<String>,
+
+    /// Also check each spec cone formulation against the atom's value
+    /// (needs the `expr-value` feature)
+    #[arg(long)]
+    check_graph: bool,
+
+    /// Cross-check classifications against a CVXPY reference JSON file
+    #[arg(long, value_name = "PATH")]
+    cvxpy_parity: Option<PathBuf>,
+
+    /// Output format for the results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+
+    /// Write the json or junit report to this file instead of stdout
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Tolerance for numeric checks, unless an atom sets numeric_tol
+    #[arg(long, default_value_t = 1e-9)]
+    tol: f64,
+
+    /// Report spec atoms that have no test expression
+    #[arg(long)]
+    coverage: bool,
+
+    /// Fail on unknown curvature or sign where the spec is concrete, on atom
+    /// names duplicated across spec categories, and on coverage gaps with --coverage
+    #[arg(long)]
+    strict: bool,
+
+    /// Number of worker threads (default: one per core; 1 runs serially)
+    #[arg(long, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Also check each atom over N random affine arguments
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    fuzz: usize,
+
+    /// Report how long each atom took to validate
+    #[arg(long)]
+    timing: bool,
+
+    /// List atoms whose validation took longer than MS milliseconds
+    #[arg(long, value_name = "MS")]
+    slow: Option<u64>,
+
+    /// List every spec atom with its category, curvature, sign and whether it
+    /// has a test expression, then exit without validating
+    #[arg(long)]
+    list_atoms: bool,
+
+    /// Compare against a previous --format json report; only new failures fail the run
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<PathBuf>,
+
+    /// Print extra detail, such as which spec file each atom came from and the
+    /// tolerance its numeric checks use
+    #[arg(long, short)]
+    verbose: bool,
 }
 
-impl CurvatureSpec {
-    fn as_str(&self) -> &str {
-        match self {
-            CurvatureSpec::Simple(s) => s.as_str(),
-            _ => "unknown",
-        }
-    }
-}
-
-/// Root structure of atoms.yaml
-#[derive(Debug, Deserialize)]
-struct AtomsYaml {
-    #[serde(default)]
-    affine_atoms: HashMap<String, AtomSpec>,
-    #[serde(default)]
-    convex_atoms: HashMap<String, AtomSpec>,
-    #[serde(default)]
-    concave_atoms: HashMap<String, AtomSpec>,
-}
-
-/// Result of a single validation check
-#[derive(Debug)]
-struct ValidationCheck {
-    name: String,
-    passed: bool,
-    message: String,
-}
-
-/// Result of validating a single atom
-#[derive(Debug)]
-struct ValidationResult {
-    atom_name: String,
-    passed: bool,
-    checks: Vec<ValidationCheck>,
+/// The specs directory of the cvx-core checkout containing this crate
+fn default_specs_dir() -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    manifest_dir.ancestors().nth(2).unwrap_or(manifest_dir).join("specs")
 }
 
-impl ValidationResult {
-    fn failed_checks(&self) -> Vec<&ValidationCheck> {
-        self.checks.iter().filter(|c| !c.passed).collect()
+/// Print a progress line, keeping stdout clean for machine-readable formats
+fn progress(format: OutputFormat, msg: &str) {
+    match format {
+        OutputFormat::Human => println!("{}", msg),
+        _ => eprintln!("{}", msg),
     }
 }
 
-/// Load atom specifications from atoms.yaml
-fn load_specs(specs_dir: &Path) -> HashMap<String, (AtomSpec, &'static str)> {
-    let atoms_path = specs_dir.join("atoms.yaml");
-    let content = fs::read_to_string(&atoms_path).expect("Failed to read atoms.yaml");
-    let data: AtomsYaml = serde_yaml::from_str(&content).expect("Failed to parse atoms.yaml");
+fn main() {
+    let cli = Cli::parse();
+    if cli.output.is_some() && cli.format == OutputFormat::Human {
+        eprintln!("--output requires --format json or --format junit");
+        std::process::exit(EXIT_INTERNAL_ERROR);
+    }
+    let filter = AtomFilter {
+        atoms: cli.atoms,
+        categories: cli.categories,
+        exclude: cli.exclude,
+    };
+    let options = ValidationOptions {
+        tol: cli.tol,
+        strict: cli.strict,
+        jobs: cli.jobs,
+        fuzz: cli.fuzz,
+        verbose: cli.verbose,
+        ..ValidationOptions::default()
+    };
 
-    let mut specs = HashMap::new();
+    progress(cli.format, "Loading CVX-Core specifications...");
 
-    for (name, mut spec) in data.affine_atoms {
-        if matches!(spec.curvature, CurvatureSpec::Unknown) {
-            spec.curvature = CurvatureSpec::Simple("affine".to_string());
+    let specs_dir = cli.specs_dir.clone().unwrap_or_else(default_specs_dir);
+    let specs = match load_specs(&specs_dir, &cli.extra_specs, cli.strict) {
+        Ok(specs) => specs,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(EXIT_INTERNAL_ERROR);
+        }
+    };
+    progress(cli.format, &format!("Loaded {} atom specifications", specs.len()));
+    if cli.verbose {
+        let mut names: Vec<&String> = specs.keys().collect();
+        names.sort();
+        for name in names {
+            let spec = &specs[name].0;
+            let numeric = if spec.skip_numeric {
+                format!("test vectors and gradient skipped, tol {:e}", spec.tol(options.tol))
+            } else {
+                format!("tol {:e}", spec.tol(options.tol))
+            };
+            let msg = format!("  {}: {} ({})", name, spec.source.display(), numeric);
+            progress(cli.format, &msg);
         }
-        specs.insert(name, (spec, "affine"));
     }
 
-    for (name, mut spec) in data.convex_atoms {
-        spec.curvature = CurvatureSpec::Simple("convex".to_string());
-        specs.insert(name, (spec, "convex"));
+    if cli.list_atoms {
+        print_atom_list(&specs, &untested_atoms(&specs, &options.registry));
+        return;
     }
 
-    for (name, mut spec) in data.concave_atoms {
-        spec.curvature = CurvatureSpec::Simple("concave".to_string());
-        specs.insert(name, (spec, "concave"));
+    progress(cli.format, "\nValidating cvxrust implementation...");
+    let mut results = match validate_all(&specs, &filter, &options) {
+        Ok(results) => results,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(EXIT_INTERNAL_ERROR);
+        }
+    };
+    let selected = |name: &str| filter.selects(name, specs.get(name).map(|(_, c)| *c));
+
+    if cli.check_graph {
+        if !cfg!(feature = "expr-value") {
+            eprintln!("--check-graph evaluates atoms and needs the expr-value feature");
+            std::process::exit(EXIT_INTERNAL_ERROR);
+        }
+        progress(cli.format, "Checking cone formulations...");
+        graph::check_graphs(&mut results, &selected);
     }
 
-    specs
-}
+    if let Some(path) = &cli.cvxpy_parity {
+        match parity::load_parity(path) {
+            Ok(reference) => {
+                let msg =
+                    format!("Checking parity against {} CVXPY classifications...", reference.len());
+                progress(cli.format, &msg);
+                let checked = parity::check_parity(
+                    &mut results,
+                    &reference,
+                    &specs,
+                    &selected,
+                    &options.registry,
+                );
+                if let Err(e) = checked {
+                    eprintln!("{}", e);
+                    std::process::exit(EXIT_INTERNAL_ERROR);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(EXIT_INTERNAL_ERROR);
+            }
+        }
+    }
 
-/// Create a test expression for the given atom
-fn create_test_expr(atom_name: &str, x: &Expr) -> Option<Expr> {
-    match atom_name {
-        // Affine atoms
-        "sum" => Some(sum(x)),
-        "reshape" => Some(reshape(x, &[1, 5])),
-        "transpose" => Some(transpose(x)),
-        "trace" => {
-            let m = variable([3, 3]);
-            Some(trace(&m))
+    let report = match cli.format {
+        OutputFormat::Human => None,
+        OutputFormat::Json => {
+            Some(serde_json::to_string_pretty(&results).expect("results serialize to JSON"))
         }
-        "diag" => Some(diag(x)),
-        "vstack" => {
-            let y = variable(5);
-            Some(vstack(vec![x.clone(), y]))
+        OutputFormat::Junit => Some(junit_report(&results)),
+    };
+    match (report, &cli.output) {
+        (None, _) => {
+            if let Err(e) = print_results(&results, &mut io::stdout().lock()) {
+                eprintln!("Failed to write report: {}", e);
+                std::process::exit(EXIT_INTERNAL_ERROR);
+            }
         }
-        "hstack" => {
-            let y = variable(5);
-            Some(hstack(vec![x.clone(), y]))
+        (Some(report), None) => println!("{}", report),
+        (Some(report), Some(path)) => {
+            if let Err(e) = fs::write(path, report + "\n") {
+                eprintln!("Failed to write {}: {}", path.display(), e);
+                std::process::exit(EXIT_INTERNAL_ERROR);
+            }
         }
+    }
 
-        // Convex atoms
-        "norm1" => Some(norm1(x)),
-        "norm2" => Some(norm2(x)),
-        "normInf" => Some(norm_inf(x)),
-        "abs" => Some(abs(x)),
-        "pos" => Some(pos(x)),
-        "neg" | "negPart" => Some(neg_part(x)),
-        "maximum" => {
-            let y = variable(5);
-            Some(max2(x, &y))
+    if cli.timing || cli.slow.is_some() {
+        if let Err(e) = print_timings(cli.format, &results, cli.timing, cli.slow) {
+            eprintln!("Failed to write timing report: {}", e);
+            std::process::exit(EXIT_INTERNAL_ERROR);
         }
-        "sum_squares" | "sumSquares" => Some(sum_squares(x)),
-        "quad_form" | "quadForm" => {
-            let p = constant(nalgebra::DMatrix::identity(5, 5));
-            Some(quad_form(x, &p))
+    }
+
+    let diff = cli.baseline.as_ref().map(|path| match baseline::load_baseline(path) {
+        Ok(before) => baseline::diff_baseline(&before, &results),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(EXIT_INTERNAL_ERROR);
         }
-        "exp" => Some(exp(x)),
-
-        // Concave atoms
-        "log" => Some(log(x)),
-        "entropy" => Some(entropy(x)),
-        "sqrt" => Some(sqrt(x)),
-        "minimum" => {
-            let y = variable(5);
-            Some(min2(x, &y))
+    });
+    if let Some(diff) = &diff {
+        let written = match cli.format {
+            OutputFormat::Human => baseline::print_baseline_diff(diff, &mut io::stdout().lock()),
+            _ => baseline::print_baseline_diff(diff, &mut io::stderr().lock()),
+        };
+        if let Err(e) = written {
+            eprintln!("Failed to write baseline report: {}", e);
+            std::process::exit(EXIT_INTERNAL_ERROR);
         }
-        "power" => Some(power(x, 0.5)), // sqrt equivalent
-
-        _ => None,
     }
-}
 
-/// Check if expression curvature matches expected
-fn check_curvature(expr: &Expr, expected: &str) -> ValidationCheck {
-    let curv = expr.curvature();
-    let actual = match curv {
-        Curvature::Constant => "constant",
-        Curvature::Affine => "affine",
-        Curvature::Convex => "convex",
-        Curvature::Concave => "concave",
-        Curvature::Unknown => "unknown",
+    let untested = if cli.coverage {
+        untested_atoms(&specs, &options.registry)
+    } else {
+        Vec::new()
     };
+    if cli.coverage {
+        print_coverage(cli.format, &untested);
+    }
 
-    let passed = match expected {
-        "constant" => curv.is_constant(),
-        "affine" => curv.is_affine(),
-        "convex" => curv.is_convex(),
-        "concave" => curv.is_concave(),
-        _ => true, // Unknown is always acceptable
+    // The summary reports how many atoms failed; the exit status only says whether any did.
+    // Against a baseline, only atoms that newly fail count.
+    let atoms_failed = match &diff {
+        Some(diff) => !diff.new_failures.is_empty(),
+        None => results.iter().any(|r| !r.passed),
     };
-
-    ValidationCheck {
-        name: "curvature".to_string(),
-        passed,
-        message: format!("expected {}, got {}", expected, actual),
+    let failed = atoms_failed || (cli.strict && !untested.is_empty());
+    if failed {
+        std::process::exit(EXIT_FAILURES);
     }
 }
 
-/// Check if expression sign matches expected
-fn check_sign(expr: &Expr, expected: &str) -> ValidationCheck {
-    let s = expr.sign();
-    let actual = match s {
-        cvxrust::dcp::Sign::Nonnegative => "nonnegative",
-        cvxrust::dcp::Sign::Nonpositive => "nonpositive",
-        cvxrust::dcp::Sign::Zero => "zero",
-        cvxrust::dcp::Sign::Unknown => "unknown",
-    };
-
-    let passed = match expected {
-        "nonnegative" => matches!(s, cvxrust::dcp::Sign::Nonnegative | cvxrust::dcp::Sign::Zero),
-        "nonpositive" => matches!(s, cvxrust::dcp::Sign::Nonpositive | cvxrust::dcp::Sign::Zero),
-        "zero" => matches!(s, cvxrust::dcp::Sign::Zero),
-        _ => true, // Unknown is always acceptable
-    };
-
-    ValidationCheck {
-        name: "sign".to_string(),
-        passed,
-        message: format!("expected {}, got {}", expected, actual),
+/// Print the coverage report, keeping stdout clean for machine-readable formats
+fn print_coverage(format: OutputFormat, untested: &[String]) {
+    if untested.is_empty() {
+        progress(format, "Coverage: every spec atom has a test expression\n");
+        return;
+    }
+    progress(format, &format!("UNTESTED SPEC ATOMS ({}):", untested.len()));
+    progress(format, &"-".repeat(40));
+    for name in untested {
+        progress(format, &format!("  {}", name));
     }
+    progress(format, "");
 }
 
-/// Validate a single atom against its specification
-fn validate_atom(atom_name: &str, spec: &AtomSpec, category: &str) -> ValidationResult {
-    let mut checks = Vec::new();
-
-    // Create test variable
-    let x = variable(5);
-
-    // Create test expression
-    let expr = match create_test_expr(atom_name, &x) {
-        Some(e) => {
-            checks.push(ValidationCheck {
-                name: "exists".to_string(),
-                passed: true,
-                message: "atom exists in cvxrust".to_string(),
-            });
-            e
-        }
-        None => {
-            checks.push(ValidationCheck {
-                name: "exists".to_string(),
-                passed: false,
-                message: format!("atom '{}' not implemented in cvxrust", atom_name),
-            });
-            return ValidationResult {
-                atom_name: atom_name.to_string(),
-                passed: false,
-                checks,
-            };
-        }
-    };
-
-    // Check curvature
-    let expected_curv = if category == "affine" {
-        "affine"
-    } else {
-        spec.curvature.as_str()
-    };
-    checks.push(check_curvature(&expr, expected_curv));
-
-    // Check sign
-    checks.push(check_sign(&expr, &spec.sign));
-
-    let all_passed = checks.iter().all(|c| c.passed);
-    ValidationResult {
-        atom_name: atom_name.to_string(),
-        passed: all_passed,
-        checks,
+/// Print the inventory of spec atoms for --list-atoms
+fn print_atom_list(specs: &HashMap<String, (AtomSpec, &str)>, untested: &[String]) {
+    let mut names: Vec<&String> = specs.keys().collect();
+    names.sort();
+
+    println!(
+        "\n{:<24} {:<9} {:<12} {:<14} STATUS",
+        "ATOM", "CATEGORY", "CURVATURE", "SIGN"
+    );
+    println!("{}", "-".repeat(72));
+    for name in names {
+        let (spec, category) = &specs[name];
+        let curvature = match &spec.curvature {
+            CurvatureSpec::Complex(rule) if !rule.rule.is_empty() => rule.rule.as_str(),
+            curvature => curvature.as_str(),
+        };
+        let status = if untested.contains(name) {
+            "not implemented"
+        } else {
+            "implemented"
+        };
+        println!(
+            "{:<24} {:<9} {:<12} {:<14} {}",
+            name, category, curvature, spec.sign, status
+        );
     }
+    let implemented = specs.len() - untested.len();
+    println!("\n{}/{} atoms have a test expression", implemented, specs.len());
 }
 
-/// Validate all atoms in cvxrust
-fn validate_all(specs: &HashMap<String, (AtomSpec, &str)>) -> Vec<ValidationResult> {
-    // List of atoms we want to validate (ones implemented in cvxrust)
-    let atoms_to_validate = vec![
-        "sum",
-        "reshape",
-        "transpose",
-        "trace",
-        "diag",
-        "vstack",
-        "hstack",
-        "norm1",
-        "norm2",
-        "normInf",
-        "abs",
-        "pos",
-        "negPart",
-        "maximum",
-        "sum_squares",
-        "quad_form",
-        "exp",
-        "log",
-        "entropy",
-        "sqrt",
-        "minimum",
-        "power",
-    ];
-
-    let mut results = Vec::new();
-
-    for atom_name in atoms_to_validate {
-        if let Some((spec, category)) = specs.get(atom_name) {
-            results.push(validate_atom(atom_name, spec, category));
-        }
+/// Print the timing reports, keeping stdout clean for machine-readable formats.
+/// Timing is observational only and never affects the exit status.
+fn print_timings(
+    format: OutputFormat,
+    results: &[ValidationResult],
+    timing: bool,
+    slow_ms: Option<u64>,
+) -> io::Result<()> {
+    let mut out: Box<dyn io::Write> = match format {
+        OutputFormat::Human => Box::new(io::stdout().lock()),
+        _ => Box::new(io::stderr().lock()),
+    };
+    if timing {
+        print_timing(results, &mut out)?;
     }
+    if let Some(ms) = slow_ms {
+        print_slow(results, Duration::from_millis(ms), &mut out)?;
+    }
+    Ok(())
+}
 
-    results
+/// Escape text for use in XML attributes and character data
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
 }
 
-/// Print validation results
-fn print_results(results: &[ValidationResult]) {
-    let passed = results.iter().filter(|r| r.passed).count();
-    let total = results.len();
-
-    println!("\n{}", "=".repeat(60));
-    println!("CVXRUST Validation Results: {}/{} atoms passed", passed, total);
-    println!("{}\n", "=".repeat(60));
-
-    // Print failures first
-    let failures: Vec<_> = results.iter().filter(|r| !r.passed).collect();
-    if !failures.is_empty() {
-        println!("FAILURES:");
-        println!("{}", "-".repeat(40));
-        for result in failures {
-            println!("\n  {}:", result.atom_name);
-            for check in result.failed_checks() {
-                println!("    FAIL {}: {}", check.name, check.message);
-            }
+/// Render results as a JUnit XML testsuite with one testcase per atom
+fn junit_report(results: &[ValidationResult]) -> String {
+    let failures = results.iter().filter(|r| !r.passed).count();
+    let total_time: f64 = results.iter().map(|r| r.duration.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuite name=\"cvxrust-atoms\" tests=\"{}\" failures=\"{}\" time=\"{:.6}\">\n",
+        results.len(),
+        failures,
+        total_time
+    ));
+    for result in results {
+        xml.push_str(&format!(
+            "  <testcase name=\"{}\" classname=\"cvxrust-atoms\" time=\"{:.6}\"",
+            xml_escape(&result.atom_name),
+            result.duration.as_secs_f64()
+        ));
+        let failed = result.failed_checks();
+        if failed.is_empty() {
+            xml.push_str("/>\n");
+            continue;
         }
-    }
-
-    // Print successes
-    let successes: Vec<_> = results.iter().filter(|r| r.passed).collect();
-    if !successes.is_empty() {
-        println!("\nPASSED ({}):", successes.len());
-        println!("{}", "-".repeat(40));
-        for result in successes {
-            println!("  {}", result.atom_name);
+        xml.push_str(">\n");
+        for check in failed {
+            let message = xml_escape(&check.message);
+            xml.push_str(&format!(
+                "    <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                xml_escape(&check.name),
+                message,
+                message
+            ));
         }
+        xml.push_str("  </testcase>\n");
     }
-
-    println!();
+    xml.push_str("</testsuite>");
+    xml
 }
 
-fn main() {
-    println!("Loading CVX-Core specifications...");
-
-    // Find specs directory (relative to cvx-core root)
-    let specs_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
-        .parent()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .join("specs");
-
-    let specs = load_specs(&specs_dir);
-    println!("Loaded {} atom specifications", specs.len());
-
-    println!("\nValidating cvxrust implementation...");
-    let results = validate_all(&specs);
-
-    print_results(&results);
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cvx_validator::ValidationCheck;
+
+    use super::*;
+
+    #[test]
+    fn xml_escape_cases() {
+        let cases = [
+            ("plain text", "plain text"),
+            ("<", "&lt;"),
+            (">", "&gt;"),
+            ("&", "&amp;"),
+            ("\"", "&quot;"),
+            ("'", "&apos;"),
+            ("<&\"'>", "&lt;&amp;&quot;&apos;&gt;"),
+            ("&lt;", "&amp;lt;"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(xml_escape(text), expected, "{}", text);
+        }
+    }
 
-    // Exit with error code if any failures
-    let failures = results.iter().filter(|r| !r.passed).count();
-    std::process::exit(failures as i32);
+    #[test]
+    fn junit_report_escapes_names_and_messages() {
+        let results = [
+            ValidationResult {
+                atom_name: "abs".to_string(),
+                passed: true,
+                checks: vec![],
+                duration: Duration::ZERO,
+            },
+            ValidationResult {
+                atom_name: "power(p=2)<&>".to_string(),
+                passed: false,
+                checks: vec![ValidationCheck {
+                    name: "curvature".to_string(),
+                    passed: false,
+                    message: "expected \"convex\", got 'unknown' & <none>".to_string(),
+                }],
+                duration: Duration::ZERO,
+            },
+        ];
+
+        let xml = junit_report(&results);
+        let message = "expected &quot;convex&quot;, got &apos;unknown&apos; &amp; &lt;none&gt;";
+        assert!(xml.contains("tests=\"2\" failures=\"1\""), "{}", xml);
+        assert!(xml.contains("<testcase name=\"abs\" classname=\"cvxrust-atoms\""), "{}", xml);
+        assert!(xml.contains("name=\"power(p=2)&lt;&amp;&gt;\""), "{}", xml);
+        assert!(
+            xml.contains(&format!(
+                "<failure type=\"curvature\" message=\"{}\">{}</failure>",
+                message, message
+            )),
+            "{}",
+            xml
+        );
+        assert!(xml.ends_with("</testsuite>"));
+    }
 }
@@ -0,0 +1,110 @@
+//! Cross-check cvxrust's classifications against CVXPY's.
+//!
+//! The reference file is JSON keyed by validator atom name, with each entry
+//! holding the `curvature` and `sign` CVXPY reports for the same test
+//! expression the validator builds, over the same argument `validate_atom`
+//! uses. Any divergence, or a build that panics, is reported as a
+//! failed "cvxpy_parity" check; a reference entry naming an atom that isn't
+//! in the specs is an error in the reference file.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use cvxrust::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    attach_check, curvature_name, sign_name, test_variable, try_build, AtomRegistry, AtomSpec,
+    ValidationCheck, ValidationResult,
+};
+
+/// Root structure of a CVXPY classification file
+#[derive(Debug, Deserialize)]
+struct ParityFile {
+    atoms: HashMap<String, AtomSpec>,
+}
+
+/// Load CVXPY reference classifications from a JSON file
+pub fn load_parity(path: &Path) -> Result<HashMap<String, AtomSpec>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let data: ParityFile = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    Ok(data.atoms)
+}
+
+fn check_atom_parity(
+    atom_name: &str,
+    spec: &AtomSpec,
+    reference: &AtomSpec,
+    registry: &AtomRegistry,
+) -> Option<ValidationCheck> {
+    let x = test_variable(spec, 5);
+    let expr = match try_build(|| registry.build(atom_name, &x)) {
+        Ok(expr) => expr?,
+        Err(e) => {
+            return Some(ValidationCheck {
+                name: "cvxpy_parity".to_string(),
+                passed: false,
+                message: format!("test expression panicked: {}", e),
+            })
+        }
+    };
+
+    let curv = curvature_name(&expr.curvature());
+    let sign = sign_name(&expr.sign());
+    let expected_curv = reference.curvature.as_str();
+
+    let mut diffs = Vec::new();
+    if curv != expected_curv {
+        diffs.push(format!("curvature: cvxpy {}, cvxrust {}", expected_curv, curv));
+    }
+    if sign != reference.sign {
+        diffs.push(format!("sign: cvxpy {}, cvxrust {}", reference.sign, sign));
+    }
+
+    Some(ValidationCheck {
+        name: "cvxpy_parity".to_string(),
+        passed: diffs.is_empty(),
+        message: if diffs.is_empty() {
+            "matches cvxpy".to_string()
+        } else {
+            diffs.join("; ")
+        },
+    })
+}
+
+/// Attach a "cvxpy_parity" check to every selected atom that has a CVXPY reference.
+///
+/// Fails without attaching anything if the reference names atoms missing from `specs`.
+pub fn check_parity(
+    results: &mut Vec<ValidationResult>,
+    reference: &HashMap<String, AtomSpec>,
+    specs: &HashMap<String, (AtomSpec, &str)>,
+    selected: &dyn Fn(&str) -> bool,
+    registry: &AtomRegistry,
+) -> Result<(), String> {
+    let mut unknown: Vec<&str> = reference
+        .keys()
+        .filter(|name| !specs.contains_key(*name))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        unknown.sort();
+        return Err(format!(
+            "CVXPY reference names atoms that are not in the specs: {}",
+            unknown.join(", ")
+        ));
+    }
+
+    let mut names: Vec<&String> = reference.keys().filter(|name| selected(name)).collect();
+    names.sort();
+
+    for name in names {
+        if let Some(check) = check_atom_parity(name, &specs[name].0, &reference[name], registry) {
+            attach_check(results, name, check);
+        }
+    }
+    Ok(())
+}
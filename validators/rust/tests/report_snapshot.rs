@@ -0,0 +1,49 @@
+//! Golden-output test for the human-readable report.
+//!
+//! Downstream scripts (validators/run_all.py among them) parse this report, so
+//! layout changes should be deliberate. After an intended change, regenerate
+//! the golden file with `UPDATE_SNAPSHOTS=1 cargo test`. A missing golden file
+//! is written from the current run, so the first run against a real cvxrust
+//! records it for review and commit.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use cvx_validator::{load_specs, print_results, validate_all, AtomFilter, ValidationOptions};
+
+#[test]
+fn human_report_matches_golden_file() {
+    let tests_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let specs =
+        load_specs(&tests_dir.join("fixtures/specs"), &[], true).expect("fixture specs load");
+
+    let filter = AtomFilter {
+        atoms: vec!["norm2".to_string(), "not_an_atom".to_string()],
+        ..AtomFilter::default()
+    };
+    let options = ValidationOptions {
+        jobs: Some(1),
+        ..ValidationOptions::default()
+    };
+    let results = validate_all(&specs, &filter, &options).expect("validation runs");
+
+    let mut out = Vec::new();
+    print_results(&results, &mut out).expect("report writes to a Vec");
+    let actual = String::from_utf8(out).expect("report is UTF-8");
+
+    let golden = tests_dir.join("snapshots/report.txt");
+    if env::var("UPDATE_SNAPSHOTS").as_deref() == Ok("1") || !golden.exists() {
+        fs::create_dir_all(golden.parent().expect("golden file has a parent"))
+            .expect("snapshot directory creates");
+        fs::write(&golden, &actual).expect("golden file writes");
+        eprintln!("wrote {}; review and commit it", golden.display());
+        return;
+    }
+
+    let expected = fs::read_to_string(&golden).expect("golden file exists");
+    assert_eq!(
+        actual, expected,
+        "report layout changed; rerun with UPDATE_SNAPSHOTS=1 if this is intended"
+    );
+}